@@ -7,23 +7,75 @@
 //! # Modules
 //!
 //! * `registro_ale`: this module converts from the registro of Ale
+//! * `ofx`: this module converts from the OFX and QFX bank statements
+use std::{fs, io};
+
 use strum_macros::{Display, EnumString};
 
+pub mod ofx;
 pub mod registro_ale;
 
-mod compatibility_errors {
+pub mod compatibility_errors {
+    use chrono::NaiveDate;
     use std::{error, fmt};
 
-    #[derive(Debug, Clone)]
-    pub struct ExtractionError;
+    /// A worksheet that does not follow the expected schema
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ExtractionError {
+        /// A cell is missing or has a value that cannot be converted
+        InvalidData,
+        /// The header has more than one column with this name
+        DuplicateColumn(String),
+        /// The account id is not mapped to an account
+        UnknownAccount(String),
+        /// A transaction date is not in the month of its worksheet
+        DateOutsideMonth(NaiveDate),
+    }
 
     impl fmt::Display for ExtractionError {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "invalid first item to double")
+            match self {
+                ExtractionError::InvalidData => write!(f, "invalid or missing cell"),
+                ExtractionError::DuplicateColumn(name) => {
+                    write!(f, "duplicate column {} in the header", name)
+                }
+                ExtractionError::UnknownAccount(id) => write!(f, "unknown account {}", id),
+                ExtractionError::DateOutsideMonth(date) => {
+                    write!(f, "date {} outside of the worksheet month", date)
+                }
+            }
         }
     }
 
     impl error::Error for ExtractionError {}
+
+    /// The workbook cannot be opened, hence no worksheet is extracted
+    #[derive(Debug)]
+    pub struct OpenError {
+        pub path: String,
+        pub reason: String,
+    }
+
+    impl fmt::Display for OpenError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "failed to open {}: {}", self.path, self.reason)
+        }
+    }
+
+    impl error::Error for OpenError {}
+
+    /// A worksheet that failed the extraction, the other ones are still extracted
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct FailedSheet {
+        pub name: String,
+        pub reason: String,
+    }
+
+    impl fmt::Display for FailedSheet {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} ({})", self.name, self.reason)
+        }
+    }
 }
 
 /// List of the supported compatibiliies with raw file
@@ -36,4 +88,109 @@ pub enum CompatibilityEnum {
     /// Version of Ale schema
     #[strum(ascii_case_insensitive)]
     Ale,
+    /// OFX or QFX bank statement
+    #[strum(ascii_case_insensitive)]
+    Ofx,
+}
+
+impl CompatibilityEnum {
+    /// Returns the extensions of the raw files of the compatibility
+    pub fn file_extensions(&self) -> &'static [&'static str] {
+        match self {
+            CompatibilityEnum::Base | CompatibilityEnum::Ale => &["xlsx"],
+            CompatibilityEnum::Ofx => &["ofx", "qfx"],
+        }
+    }
+}
+
+/// Returns the sorted paths of the files in a directory with one of the extensions
+///
+/// Extensions are compared ignoring the case. Subdirectories are not visited and
+/// the lock files that excel creates next to an open workbook, like
+/// `~$registro.xlsx`, are skipped.
+///
+/// # Errors
+///
+/// The directory cannot be read or it has no file with the extensions.
+pub fn input_paths(dir: &str, extensions: &[&str]) -> io::Result<Vec<String>> {
+    let mut paths: Vec<String> = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let has_extension = path.extension().is_some_and(|extension| {
+            extensions
+                .iter()
+                .any(|expected| extension.eq_ignore_ascii_case(expected))
+        });
+        let is_lock_file = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("~$"));
+        if path.is_file() && has_extension && !is_lock_file {
+            paths.push(path.to_string_lossy().into_owned());
+        }
+    }
+    if paths.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no {} file in {}", extensions.join(" or "), dir),
+        ));
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Sign of the incomes in the raw file
+///
+/// The registry expects positive incomes and negative expenses, amounts of
+/// raw files with the opposite convention are negated on import.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignConvention {
+    /// Incomes are positive and expenses negative
+    #[default]
+    IncomePositive,
+    /// Expenses are positive and incomes negative, like in some bank exports
+    IncomeNegative,
+}
+
+impl SignConvention {
+    /// Converts an amount of the raw file to the registry convention
+    pub fn normalize(&self, amount: f32) -> f32 {
+        match self {
+            SignConvention::IncomePositive => amount,
+            SignConvention::IncomeNegative => -amount,
+        }
+    }
+}
+
+/// Columns with the amounts of the transactions in the raw file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AmountColumns {
+    /// A single column with the signed amount
+    Signed(String),
+    /// Two columns with the expenses and the incomes as positive values, the signed
+    /// amount is the credit minus the debit and an empty cell counts as zero
+    DebitCredit { debit: String, credit: String },
+}
+
+impl Default for AmountColumns {
+    fn default() -> Self {
+        AmountColumns::Signed(String::from("Saldo"))
+    }
+}
+
+/// Check of the transaction dates against the month of their worksheet
+///
+/// Worksheets are named after a month, then a transaction of another month
+/// is likely a copy-paste error.
+#[derive(EnumString, Display, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MonthCheck {
+    /// Dates are not checked
+    #[default]
+    #[strum(serialize = "skip", ascii_case_insensitive)]
+    Skip,
+    /// Transactions of another month are logged with a warning
+    #[strum(serialize = "warn", ascii_case_insensitive)]
+    Warn,
+    /// Transactions of another month fail the extraction of their worksheet
+    #[strum(serialize = "error", ascii_case_insensitive)]
+    Error,
 }
@@ -0,0 +1,308 @@
+use crate::io::number_parser::NumberParser;
+use crate::model::account::TransactionAccountName;
+use crate::model::registry::Registry;
+use crate::model::transaction::{TransactionCategory, TransactionEvent};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+
+use super::compatibility_errors::ExtractionError;
+
+/// Build a registry from an OFX or QFX file
+///
+/// # Arguments
+///
+/// * `path`: a string slice with the path of the OFX file
+/// * `account_ids`: OFX account ids mapped to their account, ids missing from the
+///   map are parsed as account names
+///
+/// # Errors
+///
+/// The file cannot be read or it is not a valid OFX statement, see `retrieve_transactions`.
+pub fn build_registry(
+    path: &str,
+    account_ids: &HashMap<String, TransactionAccountName>,
+) -> Result<Registry, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)?;
+    let transactions = retrieve_transactions(&content, account_ids)?;
+
+    Ok(Registry::from_transactions(transactions, None))
+}
+
+/// Retrieve the transactions of the `<STMTTRN>` entries of an OFX file
+///
+/// Both the SGML files of OFX 1.x, whose leaf elements have no closing tag, and
+/// the XML files of OFX 2.x are supported: the header is skipped and the closing
+/// tags of the leaf elements are ignored and the XML entities of their values,
+/// like `&amp;`, are decoded.
+/// The account of a transaction is the `<ACCTID>` of the `<BANKACCTFROM>` or
+/// `<CCACCTFROM>` of its statement, the ids of other aggregates, like the
+/// `<BANKACCTTO>` of a transfer, are ignored. OFX has no
+/// categories, then transactions are uncategorized and their description joins
+/// the `<NAME>` and the `<MEMO>`.
+///
+/// # Parameters
+///
+/// * `content`: content of the OFX file
+/// * `account_ids`: OFX account ids mapped to their account
+///
+/// # Returns
+///
+/// * Vector of transaction events extracted from the statements
+fn retrieve_transactions(
+    content: &str,
+    account_ids: &HashMap<String, TransactionAccountName>,
+) -> Result<Vec<TransactionEvent>, ExtractionError> {
+    let body_start = content.find("<OFX>").ok_or(ExtractionError::InvalidData)?;
+
+    let mut transactions: Vec<TransactionEvent> = Vec::new();
+    let mut account: Option<TransactionAccountName> = None;
+    // True inside the aggregate with the account of the statement
+    let mut in_statement_account = false;
+    // Leaf elements of the current <STMTTRN>, None outside of a transaction
+    let mut entry: Option<HashMap<String, String>> = None;
+
+    for element in content[body_start..].split('<').skip(1) {
+        let (tag, value) = element
+            .split_once('>')
+            .ok_or(ExtractionError::InvalidData)?;
+        let tag = tag.trim().to_uppercase();
+        let value = decode_entities(value.trim());
+        let value = value.as_str();
+        match tag.as_str() {
+            "STMTTRN" => entry = Some(HashMap::new()),
+            "/STMTTRN" => {
+                let fields = entry.take().ok_or(ExtractionError::InvalidData)?;
+                let account = account.clone().ok_or(ExtractionError::InvalidData)?;
+                transactions.push(parse_transaction(&fields, account)?);
+            }
+            "BANKACCTFROM" | "CCACCTFROM" => in_statement_account = true,
+            "/BANKACCTFROM" | "/CCACCTFROM" => in_statement_account = false,
+            "ACCTID" if in_statement_account => account = Some(parse_account(value, account_ids)?),
+            _ if tag.starts_with('/') || value.is_empty() => {}
+            _ => {
+                if let Some(fields) = entry.as_mut() {
+                    fields.insert(tag, String::from(value));
+                }
+            }
+        }
+    }
+    Ok(transactions)
+}
+
+/// Replaces the predefined XML entities of a value with their characters,
+/// e.g. "AT&amp;T" is "AT&T"
+fn decode_entities(value: &str) -> String {
+    // &amp; is the last one, so that "&amp;lt;" is "&lt;" and not "<"
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Parses the amount of a transaction
+///
+/// OFX amounts have a decimal point, but some banks write them with a decimal
+/// comma: the last separator is taken as the decimal one, e.g. both "1,234.56"
+/// and "1.234,56" are 1234.56, falling back to the other format.
+fn parse_amount(value: &str) -> Option<f32> {
+    let (parser, fallback) = if value.rfind(',') > value.rfind('.') {
+        (NumberParser::EUROPEAN, NumberParser::US)
+    } else {
+        (NumberParser::US, NumberParser::EUROPEAN)
+    };
+    parser.parse(value).or_else(|| fallback.parse(value))
+}
+
+/// Returns the account of an OFX account id
+fn parse_account(
+    account_id: &str,
+    account_ids: &HashMap<String, TransactionAccountName>,
+) -> Result<TransactionAccountName, ExtractionError> {
+    match account_ids.get(account_id) {
+        Some(account) => Ok(account.clone()),
+        None => TransactionAccountName::from_str(account_id)
+            .map_err(|_| ExtractionError::UnknownAccount(String::from(account_id))),
+    }
+}
+
+/// Build a transaction from the leaf elements of a `<STMTTRN>`
+///
+/// Dates are in the `YYYYMMDD[HHMMSS[.XXX][[offset:TZ]]]` format of OFX, only
+/// the day is kept.
+fn parse_transaction(
+    fields: &HashMap<String, String>,
+    account: TransactionAccountName,
+) -> Result<TransactionEvent, ExtractionError> {
+    let date = fields
+        .get("DTPOSTED")
+        .and_then(|value| value.get(..8))
+        .and_then(|value| NaiveDate::parse_from_str(value, "%Y%m%d").ok())
+        .ok_or(ExtractionError::InvalidData)?;
+
+    let amount = fields
+        .get("TRNAMT")
+        .and_then(|value| parse_amount(value))
+        .ok_or(ExtractionError::InvalidData)?;
+
+    let description: Vec<&str> = ["NAME", "MEMO"]
+        .iter()
+        .filter_map(|tag| fields.get(*tag).map(String::as_str))
+        .collect();
+    let description = if description.is_empty() {
+        None
+    } else {
+        Some(description.join(" - "))
+    };
+
+    Ok(TransactionEvent::new(
+        date,
+        amount,
+        TransactionCategory::Uncategorized,
+        description,
+        account,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+
+    use crate::{
+        compatibility::compatibility_errors::ExtractionError,
+        model::account::TransactionAccountName,
+    };
+
+    use super::{parse_amount, retrieve_transactions};
+
+    const XML_STATEMENT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<?OFX OFXHEADER="200" VERSION="220" SECURITY="NONE" OLDFILEUID="NONE" NEWFILEUID="NONE"?>
+<OFX>
+  <BANKMSGSRSV1>
+    <STMTTRNRS>
+      <STMTRS>
+        <CURDEF>EUR</CURDEF>
+        <BANKACCTFROM>
+          <BANKID>12345</BANKID>
+          <ACCTID>IT60X0542811101000000123456</ACCTID>
+          <ACCTTYPE>CHECKING</ACCTTYPE>
+        </BANKACCTFROM>
+        <BANKTRANLIST>
+          <DTSTART>20230501</DTSTART>
+          <DTEND>20230531</DTEND>
+          <STMTTRN>
+            <TRNTYPE>CREDIT</TRNTYPE>
+            <DTPOSTED>20230502120000.000[+1:CET]</DTPOSTED>
+            <TRNAMT>1500.00</TRNAMT>
+            <FITID>1</FITID>
+            <NAME>ACME SPA</NAME>
+            <MEMO>Stipendio</MEMO>
+          </STMTTRN>
+          <STMTTRN>
+            <TRNTYPE>DEBIT</TRNTYPE>
+            <DTPOSTED>20230504</DTPOSTED>
+            <TRNAMT>-25,50</TRNAMT>
+            <FITID>2</FITID>
+          </STMTTRN>
+        </BANKTRANLIST>
+      </STMTRS>
+    </STMTTRNRS>
+  </BANKMSGSRSV1>
+</OFX>
+"#;
+
+    #[test]
+    fn xml_statement() {
+        let account_ids = HashMap::from([(
+            String::from("IT60X0542811101000000123456"),
+            TransactionAccountName::Ale,
+        )]);
+        let transactions = retrieve_transactions(XML_STATEMENT, &account_ids).unwrap();
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(
+            transactions[0].date,
+            NaiveDate::from_ymd_opt(2023, 5, 2).unwrap()
+        );
+        assert_eq!(transactions[0].amount, 1500.0);
+        assert_eq!(
+            transactions[0].description.as_deref(),
+            Some("ACME SPA - Stipendio")
+        );
+        assert_eq!(transactions[1].amount, -25.5);
+        assert_eq!(transactions[1].description, None);
+        assert!(transactions
+            .iter()
+            .all(|t| t.account == TransactionAccountName::Ale));
+    }
+
+    #[test]
+    fn amounts() {
+        for (value, expected) in [
+            ("1500.00", 1500.0),
+            ("-25,50", -25.5),
+            ("1,234.56", 1234.56),
+            ("-1.234,56", -1234.56),
+            ("1.234.567", 1234567.0),
+        ] {
+            assert_eq!(parse_amount(value), Some(expected), "{value}");
+        }
+        assert_eq!(parse_amount("abc"), None);
+    }
+
+    #[test]
+    fn thousands_separators_and_entities() {
+        let statement = XML_STATEMENT
+            .replace("<TRNAMT>1500.00</TRNAMT>", "<TRNAMT>1,500.00</TRNAMT>")
+            .replace("<NAME>ACME SPA</NAME>", "<NAME>AT&amp;T &lt;IT&gt;</NAME>");
+        let account_ids = HashMap::from([(
+            String::from("IT60X0542811101000000123456"),
+            TransactionAccountName::Ale,
+        )]);
+        let transactions = retrieve_transactions(&statement, &account_ids).unwrap();
+
+        assert_eq!(transactions[0].amount, 1500.0);
+        assert_eq!(
+            transactions[0].description.as_deref(),
+            Some("AT&T <IT> - Stipendio")
+        );
+    }
+
+    #[test]
+    fn unknown_account() {
+        assert_eq!(
+            retrieve_transactions(XML_STATEMENT, &HashMap::new()).err(),
+            Some(ExtractionError::UnknownAccount(String::from(
+                "IT60X0542811101000000123456"
+            )))
+        );
+    }
+
+    #[test]
+    fn transfer_destination_account() {
+        let statement = XML_STATEMENT.replace(
+            "<FITID>2</FITID>",
+            "<FITID>2</FITID>
+            <BANKACCTTO>
+              <BANKID>54321</BANKID>
+              <ACCTID>IT60X0542811101000000654321</ACCTID>
+              <ACCTTYPE>SAVINGS</ACCTTYPE>
+            </BANKACCTTO>",
+        );
+        let account_ids = HashMap::from([(
+            String::from("IT60X0542811101000000123456"),
+            TransactionAccountName::Ale,
+        )]);
+        let transactions = retrieve_transactions(&statement, &account_ids).unwrap();
+
+        assert_eq!(transactions.len(), 2);
+        assert!(transactions
+            .iter()
+            .all(|t| t.account == TransactionAccountName::Ale));
+    }
+}
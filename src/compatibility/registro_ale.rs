@@ -1,14 +1,21 @@
+use crate::io::number_parser::NumberParser;
 use crate::model::account::{Account, TransactionAccountName};
 use crate::model::registry::Registry;
 use crate::model::transaction::{TransactionCategory, TransactionEvent};
 use calamine::{open_workbook, DataType, Range, Reader, Xlsx};
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate};
 use indicatif::{MultiProgress, ProgressBar, ProgressIterator, ProgressStyle};
+use log::warn;
+use rayon::prelude::*;
 use regex::Regex;
 use std::collections::HashMap;
+use std::io;
 use std::str::FromStr;
 
-use super::compatibility_errors::ExtractionError;
+use super::{
+    compatibility_errors::{ExtractionError, FailedSheet, OpenError},
+    input_paths, AmountColumns, CompatibilityEnum, MonthCheck, SignConvention,
+};
 
 /// Build a registry from a excel file composed of many sheets
 ///
@@ -16,43 +23,196 @@ use super::compatibility_errors::ExtractionError;
 ///
 /// * `path`: a string slice with the path of the excel file
 /// * `worksheet_template`: the regular expression that defines valid worksheets
+/// * `sign_convention`: sign of the incomes in the excel file
+/// * `amount_columns`: columns with the amounts of the transactions
+/// * `month_check`: check of the transaction dates against the worksheet month
+/// * `category_aliases`: raw category names mapped to their category
 ///
 /// # Return
 ///
 /// It returns a Tuple with two entries:
-/// * `Registry`: the extracted registry
-/// * `Vec<String>`: vector containing failed registry extractions
+/// * `Registry`: the registry extracted from the valid worksheets
+/// * `Vec<FailedSheet>`: vector containing failed worksheet extractions
+///
+/// # Errors
+///
+/// Only a workbook that cannot be opened is an error, worksheets that fail the
+/// extraction are skipped and returned in the `FailedSheet` vector.
 pub fn build_registry_batch(
     path: &str,
     worksheet_template: Regex,
-) -> Result<(Registry, Vec<String>), Box<dyn std::error::Error>> {
-    let workbook: Xlsx<_> = open_workbook(path)?;
-    let mut sheet_names = workbook.sheet_names().to_vec();
-    // We sort the sheet names to keep the registries ordered by time
-    sheet_names.sort();
+    sign_convention: SignConvention,
+    amount_columns: &AmountColumns,
+    month_check: MonthCheck,
+    category_aliases: &HashMap<String, TransactionCategory>,
+) -> Result<(Registry, Vec<FailedSheet>), OpenError> {
+    let sheet_names = matching_sheet_names(path, &worksheet_template)?;
 
     let multi_progress = MultiProgress::new();
     let progress_bar = multi_progress.add(ProgressBar::new(sheet_names.len() as u64));
 
-    // create the two resulting structures that will be filled during the for loop
-    let mut failed_extractions: Vec<String> = Vec::new();
-    let mut result_registry = Registry::new(None);
+    let extractions = sheet_names
+        .into_iter()
+        .progress_with(progress_bar)
+        .map(|worksheet| {
+            let extraction = build_registry(
+                path,
+                &worksheet,
+                sign_convention,
+                amount_columns,
+                month_check,
+                category_aliases,
+                &multi_progress,
+            );
+            (worksheet, extraction.map_err(|e| e.to_string()))
+        })
+        .collect();
+    Ok(merge_extractions(extractions))
+}
 
-    // for loop that extract each registry at a time
-    for worksheet in sheet_names.iter().progress_with(progress_bar) {
-        if worksheet_template.is_match(worksheet) {
-            result_registry = match build_registry(path, worksheet, &multi_progress) {
-                Ok(new_registry) => result_registry + new_registry,
-                Err(_) => {
-                    failed_extractions.push(worksheet.clone());
-                    result_registry
-                }
-            };
-        }
+/// Build a registry from a excel file composed of many sheets extracting them in parallel
+///
+/// Each worksheet is read on its own thread, then the registries are merged in
+/// the order of the sheet names, hence the result is the same of `build_registry_batch`.
+///
+/// # Arguments
+///
+/// * `path`: a string slice with the path of the excel file
+/// * `worksheet_template`: the regular expression that defines valid worksheets
+/// * `sign_convention`: sign of the incomes in the excel file
+/// * `amount_columns`: columns with the amounts of the transactions
+/// * `month_check`: check of the transaction dates against the worksheet month
+/// * `category_aliases`: raw category names mapped to their category
+pub fn build_registry_batch_parallel(
+    path: &str,
+    worksheet_template: Regex,
+    sign_convention: SignConvention,
+    amount_columns: &AmountColumns,
+    month_check: MonthCheck,
+    category_aliases: &HashMap<String, TransactionCategory>,
+) -> Result<(Registry, Vec<FailedSheet>), OpenError> {
+    let sheet_names = matching_sheet_names(path, &worksheet_template)?;
+
+    let multi_progress = MultiProgress::new();
+    let progress_bar = multi_progress.add(ProgressBar::new(sheet_names.len() as u64));
+
+    let extractions = sheet_names
+        .into_par_iter()
+        .map(|worksheet| {
+            let extraction = build_registry(
+                path,
+                &worksheet,
+                sign_convention,
+                amount_columns,
+                month_check,
+                category_aliases,
+                &multi_progress,
+            );
+            progress_bar.inc(1);
+            (worksheet, extraction.map_err(|e| e.to_string()))
+        })
+        .collect();
+    progress_bar.finish();
+    Ok(merge_extractions(extractions))
+}
+
+/// Paths of the excel files with their failed worksheet extractions
+pub type FailedFiles = Vec<(String, Vec<FailedSheet>)>;
+
+/// Build a registry from many excel files, e.g. a workbook for each year
+///
+/// Each file is extracted with `build_registry_batch_parallel`, then the registries
+/// are merged in the order of the paths.
+///
+/// # Arguments
+///
+/// * `paths`: paths of the excel files
+/// * `worksheet_template`: the regular expression that defines valid worksheets
+/// * `sign_convention`: sign of the incomes in the excel files
+/// * `amount_columns`: columns with the amounts of the transactions
+/// * `month_check`: check of the transaction dates against the worksheet month
+/// * `category_aliases`: raw category names mapped to their category
+///
+/// # Return
+///
+/// It returns a Tuple with two entries:
+/// * `Registry`: the registry merging the valid worksheets of all the files
+/// * `FailedFiles`: each path with its failed worksheet extractions
+///
+/// # Errors
+///
+/// The first file that cannot be opened, see `build_registry_batch`.
+pub fn build_registry_files(
+    paths: &[String],
+    worksheet_template: Regex,
+    sign_convention: SignConvention,
+    amount_columns: &AmountColumns,
+    month_check: MonthCheck,
+    category_aliases: &HashMap<String, TransactionCategory>,
+) -> Result<(Registry, FailedFiles), OpenError> {
+    let mut result_registry = Registry::new(None);
+    let mut failed_extractions: FailedFiles = Vec::new();
+    for path in paths {
+        let (registry, failed_sheets) = build_registry_batch_parallel(
+            path,
+            worksheet_template.clone(),
+            sign_convention,
+            amount_columns,
+            month_check,
+            category_aliases,
+        )?;
+        result_registry.extend(registry);
+        failed_extractions.push((path.clone(), failed_sheets));
     }
     Ok((result_registry, failed_extractions))
 }
 
+/// Returns the sorted paths of the excel files in a directory, see `input_paths`
+///
+/// # Errors
+///
+/// The directory cannot be read or it has no excel file.
+pub fn spreadsheet_paths(dir: &str) -> io::Result<Vec<String>> {
+    input_paths(dir, CompatibilityEnum::Ale.file_extensions())
+}
+
+/// Returns the sorted names of the worksheets that match the template
+fn matching_sheet_names(path: &str, worksheet_template: &Regex) -> Result<Vec<String>, OpenError> {
+    let workbook: Xlsx<_> = open_workbook(path).map_err(|e: calamine::XlsxError| OpenError {
+        path: String::from(path),
+        reason: e.to_string(),
+    })?;
+    let mut sheet_names: Vec<String> = workbook
+        .sheet_names()
+        .iter()
+        .filter(|name| worksheet_template.is_match(name))
+        .cloned()
+        .collect();
+    // We sort the sheet names to keep the registries ordered by time
+    sheet_names.sort();
+    Ok(sheet_names)
+}
+
+/// Merges the registries extracted from the worksheets in the given order
+///
+/// Worksheets whose extraction failed are returned as `FailedSheet`.
+fn merge_extractions(
+    extractions: Vec<(String, Result<Registry, String>)>,
+) -> (Registry, Vec<FailedSheet>) {
+    let mut failed_extractions: Vec<FailedSheet> = Vec::new();
+    let mut result_registry = Registry::new(None);
+    for (worksheet, extraction) in extractions {
+        match extraction {
+            Ok(new_registry) => result_registry.extend(new_registry),
+            Err(reason) => failed_extractions.push(FailedSheet {
+                name: worksheet,
+                reason,
+            }),
+        };
+    }
+    (result_registry, failed_extractions)
+}
+
 /// Build the Registry strut from the excel file.
 ///
 /// First of all loads the excel, then extracts from the first row
@@ -63,6 +223,11 @@ pub fn build_registry_batch(
 ///
 /// * `path`: path of the excel file
 /// * `worksheet`: name of the worksheet file
+/// * `sign_convention`: sign of the incomes in the excel file
+/// * `amount_columns`: columns with the amounts of the transactions
+/// * `month_check`: check of the transaction dates against the worksheet month,
+///   see `check_transaction_months`
+/// * `category_aliases`: raw category names mapped to their category
 /// * `multi_progress`: MultiProgress struct used to plot the progress bar
 ///
 /// # Returns
@@ -71,6 +236,10 @@ pub fn build_registry_batch(
 pub fn build_registry(
     path: &str,
     worksheet: &str,
+    sign_convention: SignConvention,
+    amount_columns: &AmountColumns,
+    month_check: MonthCheck,
+    category_aliases: &HashMap<String, TransactionCategory>,
     multi_progress: &MultiProgress,
 ) -> Result<Registry, Box<dyn std::error::Error>> {
     let mut spinner = ProgressBar::new_spinner();
@@ -95,34 +264,79 @@ pub fn build_registry(
 
     spinner.set_message(format!("Extracting {worksheet}"));
 
-    let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
-    let range = workbook.worksheet_range(worksheet).unwrap()?;
+    let mut workbook: Xlsx<_> = open_workbook(path)?;
+    let range = workbook
+        .worksheet_range(worksheet)
+        .ok_or(ExtractionError::InvalidData)??;
 
-    let transactions = retrieve_transactions(&range)?;
+    let transactions =
+        retrieve_transactions(&range, sign_convention, amount_columns, category_aliases)?;
+    check_transaction_months(worksheet, &transactions, month_check)?;
     let accounts = retrieve_accounts(worksheet, &range)?;
 
-    let mut registry = Registry::new(Some(accounts));
-    registry.add_batch(transactions);
+    let registry = Registry::from_transactions(transactions, Some(accounts));
 
     spinner.finish_with_message(format!("{worksheet} done"));
     Ok(registry)
 }
 
+/// Returns the first day of the month of a worksheet named like 2023-05
+fn worksheet_month(worksheet: &str) -> Result<NaiveDate, ExtractionError> {
+    NaiveDate::from_str(&format!("{worksheet}-01")).map_err(|_| ExtractionError::InvalidData)
+}
+
+/// Checks that the transactions are dated in the month of their worksheet
+///
+/// With `MonthCheck::Warn` each transaction of another month is logged, with
+/// `MonthCheck::Error` the first one is a `DateOutsideMonth` error.
+fn check_transaction_months(
+    worksheet: &str,
+    transactions: &[TransactionEvent],
+    month_check: MonthCheck,
+) -> Result<(), ExtractionError> {
+    if month_check == MonthCheck::Skip {
+        return Ok(());
+    }
+    let month = worksheet_month(worksheet)?;
+    for transaction in transactions
+        .iter()
+        .filter(|t| t.date.year() != month.year() || t.date.month() != month.month())
+    {
+        if month_check == MonthCheck::Error {
+            return Err(ExtractionError::DateOutsideMonth(transaction.date));
+        }
+        warn!(
+            "Transaction of {} in the worksheet {}, is it a copy-paste error?",
+            transaction.date, worksheet
+        );
+    }
+    Ok(())
+}
+
 /// Retrieve transactions from the worksheet
 ///
-/// The first row contains the columns and the iteration gets their positions.
+/// The first row contains the columns and the iteration gets their positions,
+/// a column name repeated in the header is a `DuplicateColumn` error.
 /// Then, the second iteration retreive the data from each row and creates transaction
 /// structs.
 ///
 /// # Parameters
 ///
 /// * `range`: calamine::Range that represents a set of rows in the worksheet
+/// * `sign_convention`: sign of the incomes in the worksheet, amounts are
+///   converted to positive incomes
+/// * `amount_columns`: columns with the amounts of the transactions
+/// * `category_aliases`: raw category names mapped to their category, they are
+///   looked up before parsing the category name
 ///
 /// # Returns
 ///
 /// * Vector of transaction events extracted from the worksheet
 fn retrieve_transactions(
     range: &Range<DataType>,
+    sign_convention: SignConvention,
+    amount_columns: &AmountColumns,
+    category_aliases: &HashMap<String, TransactionCategory>,
 ) -> Result<Vec<TransactionEvent>, ExtractionError> {
     let mut transactions: Vec<TransactionEvent> = Vec::new();
     let mut columns_positions: HashMap<String, usize> = HashMap::new();
@@ -136,50 +350,63 @@ fn retrieve_transactions(
                 if *cell == DataType::Empty {
                     break;
                 }
-                columns_positions.insert(cell.to_string(), col_index);
+                let name = cell.to_string();
+                if columns_positions.insert(name.clone(), col_index).is_some() {
+                    return Err(ExtractionError::DuplicateColumn(name));
+                }
             }
         } else {
             let date = row
-                .get(*columns_positions.get("Data").ok_or(ExtractionError)?)
-                .ok_or(ExtractionError)?
+                .get(
+                    *columns_positions
+                        .get("Data")
+                        .ok_or(ExtractionError::InvalidData)?,
+                )
+                .ok_or(ExtractionError::InvalidData)?
                 .as_date()
-                .ok_or(ExtractionError)?;
+                .ok_or(ExtractionError::InvalidData)?;
 
-            let amount = row
-                .get(*columns_positions.get("Saldo").ok_or(ExtractionError)?)
-                .ok_or(ExtractionError)?
-                .get_float()
-                .ok_or(ExtractionError)? as f32;
+            let amount = parse_amount(row, &columns_positions, amount_columns)?;
+            let amount = sign_convention.normalize(amount);
 
-            let category = row
-                .get(*columns_positions.get("Categoria").ok_or(ExtractionError)?)
-                .ok_or(ExtractionError)?
-                .get_string()
-                .ok_or(ExtractionError)?;
+            let category = parse_category(
+                row.get(
+                    *columns_positions
+                        .get("Categoria")
+                        .ok_or(ExtractionError::InvalidData)?,
+                )
+                .ok_or(ExtractionError::InvalidData)?,
+                category_aliases,
+            );
 
             let description = row
-                .get(*columns_positions.get("Nota").ok_or(ExtractionError)?)
-                .ok_or(ExtractionError)?
+                .get(
+                    *columns_positions
+                        .get("Nota")
+                        .ok_or(ExtractionError::InvalidData)?,
+                )
+                .ok_or(ExtractionError::InvalidData)?
                 .get_string()
                 .map(String::from);
 
             let account = row
-                .get(*columns_positions.get("Conto").ok_or(ExtractionError)?)
-                .ok_or(ExtractionError)?
+                .get(
+                    *columns_positions
+                        .get("Conto")
+                        .ok_or(ExtractionError::InvalidData)?,
+                )
+                .ok_or(ExtractionError::InvalidData)?
                 .get_string()
-                .ok_or(ExtractionError)?;
+                .ok_or(ExtractionError::InvalidData)?;
 
             let transaction = TransactionEvent::new(
                 date,
                 amount,
-                match TransactionCategory::from_str(category) {
-                    Ok(c) => c,
-                    Err(_) => return Err(ExtractionError),
-                },
+                category,
                 description,
                 match TransactionAccountName::from_str(account) {
                     Ok(d) => d,
-                    Err(_) => return Err(ExtractionError),
+                    Err(_) => return Err(ExtractionError::InvalidData),
                 },
             );
             transactions.push(transaction);
@@ -188,10 +415,87 @@ fn retrieve_transactions(
     Ok(transactions)
 }
 
+/// Returns the amount of a cell
+///
+/// Amounts written as text, e.g. "1.234,56€", are parsed with the European format.
+fn cell_amount(cell: &DataType) -> Option<f32> {
+    match cell.get_float() {
+        Some(amount) => Some(amount as f32),
+        None => cell
+            .get_string()
+            .and_then(|text| NumberParser::default().parse(text)),
+    }
+}
+
+/// Parse the amount of a transaction from its row
+///
+/// With `AmountColumns::DebitCredit` the amount is the credit minus the debit,
+/// one of the two cells can be empty but not both of them.
+fn parse_amount(
+    row: &[DataType],
+    columns_positions: &HashMap<String, usize>,
+    amount_columns: &AmountColumns,
+) -> Result<f32, ExtractionError> {
+    let cell = |name: &str| -> Result<&DataType, ExtractionError> {
+        row.get(
+            *columns_positions
+                .get(name)
+                .ok_or(ExtractionError::InvalidData)?,
+        )
+        .ok_or(ExtractionError::InvalidData)
+    };
+    match amount_columns {
+        AmountColumns::Signed(name) => cell_amount(cell(name)?).ok_or(ExtractionError::InvalidData),
+        AmountColumns::DebitCredit { debit, credit } => {
+            let (debit, credit) = (cell(debit)?, cell(credit)?);
+            if *debit == DataType::Empty && *credit == DataType::Empty {
+                return Err(ExtractionError::InvalidData);
+            }
+            let value = |cell: &DataType| match cell {
+                DataType::Empty => Ok(0.0),
+                _ => cell_amount(cell).ok_or(ExtractionError::InvalidData),
+            };
+            Ok(value(credit)? - value(debit)?)
+        }
+    }
+}
+
+/// Parse the category of a transaction from its cell
+///
+/// A category name in `category_aliases` gets the aliased category, e.g. both
+/// "supermercato" and "spesa" can be `TransactionCategory::Spesa`.
+/// Empty or unknown categories do not discard the transaction, which is
+/// assigned to `TransactionCategory::Uncategorized` with a warning.
+fn parse_category(
+    cell: &DataType,
+    category_aliases: &HashMap<String, TransactionCategory>,
+) -> TransactionCategory {
+    match cell.get_string() {
+        Some(category) => match category_aliases.get(category) {
+            Some(aliased) => aliased.clone(),
+            None => TransactionCategory::from_str(category).unwrap_or_else(|_| {
+                warn!(
+                    "Unknown category {}, the transaction is uncategorized",
+                    category
+                );
+                TransactionCategory::Uncategorized
+            }),
+        },
+        None => {
+            warn!("Missing category, the transaction is uncategorized");
+            TransactionCategory::Uncategorized
+        }
+    }
+}
+
 /// Retrieve accounts from the worksheet
 ///
 /// The accounts information are stored in a table near the the transaction one.
 /// Therefore, the column definition step needs to go beyond the first set of non empty columns.
+/// The table is optional: a worksheet without the "Conti corrente" column gives no accounts,
+/// and the registry builds them from the transactions.
+/// The initial values are dated with the optional "Data saldo iniziale" column, falling back
+/// to the first day of the worksheet month.
 ///
 /// # Parameters
 ///
@@ -205,12 +509,7 @@ fn retrieve_accounts(
     worksheet: &str,
     range: &Range<DataType>,
 ) -> Result<Vec<Account>, ExtractionError> {
-    let mut date_str = String::from(worksheet);
-    date_str.push_str("-01");
-    let date = match NaiveDate::from_str(&date_str) {
-        Ok(d) => d,
-        Err(_) => return Err(ExtractionError),
-    };
+    let date = worksheet_month(worksheet)?;
 
     let mut accounts: Vec<Account> = Vec::new();
 
@@ -230,18 +529,24 @@ fn retrieve_accounts(
                 }
 
                 if in_second_block & !empty_cell {
-                    columns_positions.insert(cell.to_string(), col_index);
+                    let name = cell.to_string();
+                    if columns_positions.insert(name.clone(), col_index).is_some() {
+                        return Err(ExtractionError::DuplicateColumn(name));
+                    }
                 }
             }
+            if !columns_positions.contains_key("Conti corrente") {
+                return Ok(accounts);
+            }
         } else {
             // If we get empty column corresponding to Conti corrente then we stop the iteration
             let cell = row
                 .get(
                     *columns_positions
                         .get("Conti corrente")
-                        .ok_or(ExtractionError)?,
+                        .ok_or(ExtractionError::InvalidData)?,
                 )
-                .ok_or(ExtractionError)?;
+                .ok_or(ExtractionError::InvalidData)?;
             if *cell == DataType::Empty {
                 break;
             }
@@ -250,28 +555,217 @@ fn retrieve_accounts(
                 &row.get(
                     *columns_positions
                         .get("Conti corrente")
-                        .ok_or(ExtractionError)?,
+                        .ok_or(ExtractionError::InvalidData)?,
                 )
-                .ok_or(ExtractionError)?
+                .ok_or(ExtractionError::InvalidData)?
                 .to_string(),
             ) {
                 Ok(a) => a,
-                Err(_) => return Err(ExtractionError),
+                Err(_) => return Err(ExtractionError::InvalidData),
             };
 
             let saldo_iniziale = row
                 .get(
                     *columns_positions
                         .get("Saldo iniziale")
-                        .ok_or(ExtractionError)?,
+                        .ok_or(ExtractionError::InvalidData)?,
                 )
-                .ok_or(ExtractionError)?
+                .ok_or(ExtractionError::InvalidData)?
                 .get_float()
-                .ok_or(ExtractionError)? as f32;
+                .ok_or(ExtractionError::InvalidData)? as f32;
+
+            // The optional start date of the account is more precise than the worksheet one
+            let account_date = columns_positions
+                .get("Data saldo iniziale")
+                .and_then(|position| row.get(*position))
+                .and_then(|cell| cell.as_date())
+                .unwrap_or(date);
 
-            let account = Account::new(account_name, saldo_iniziale, date);
+            let account = Account::new(account_name, saldo_iniziale, account_date);
             accounts.push(account);
         }
     }
     Ok(accounts)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use calamine::{open_workbook, DataType, Range, Reader, Xlsx};
+    use chrono::NaiveDate;
+    use indicatif::MultiProgress;
+    use log::{LevelFilter, Log, Metadata, Record};
+
+    use crate::{
+        compatibility::{AmountColumns, MonthCheck, SignConvention},
+        model::transaction::TransactionCategory,
+    };
+
+    use super::{build_registry, retrieve_accounts, retrieve_transactions};
+
+    /// Warnings logged by the tests
+    static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    /// Logger that collects the warnings in `WARNINGS`
+    struct WarningLogger;
+
+    impl Log for WarningLogger {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &Record) {
+            if self.enabled(record.metadata()) {
+                WARNINGS.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Returns a sheet with the header of the transactions and one row of the
+    /// Ale account dated 2023-07-03 for each pair of amount and category
+    fn sheet(rows: &[(DataType, DataType)]) -> Range<DataType> {
+        let mut range = Range::new((0, 0), (rows.len() as u32, 4));
+        for (col, name) in ["Data", "Saldo", "Categoria", "Nota", "Conto"]
+            .iter()
+            .enumerate()
+        {
+            range.set_value((0, col as u32), DataType::String(String::from(*name)));
+        }
+        for (row, (amount, category)) in rows.iter().enumerate() {
+            let row = row as u32 + 1;
+            // 2023-07-03 as days since the excel epoch
+            range.set_value((row, 0), DataType::DateTime(45110.0));
+            range.set_value((row, 1), amount.clone());
+            range.set_value((row, 2), category.clone());
+            range.set_value((row, 3), DataType::Empty);
+            range.set_value((row, 4), DataType::String(String::from("Ale")));
+        }
+        range
+    }
+
+    #[test]
+    fn unknown_category() {
+        let range = sheet(&[
+            (
+                DataType::Float(-12.5),
+                DataType::String(String::from("Anything")),
+            ),
+            (DataType::Float(-12.5), DataType::Empty),
+        ]);
+
+        let transactions = retrieve_transactions(
+            &range,
+            SignConvention::IncomePositive,
+            &AmountColumns::default(),
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(transactions.len(), 2);
+        for transaction in transactions {
+            assert_eq!(transaction.category, TransactionCategory::Uncategorized);
+            assert_eq!(
+                transaction.date,
+                NaiveDate::from_ymd_opt(2023, 7, 3).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn category_aliases() {
+        let range = sheet(&["supermercato", "alimentari", "Affitto"].map(|category| {
+            (
+                DataType::Float(-12.5),
+                DataType::String(String::from(category)),
+            )
+        }));
+        let aliases = HashMap::from([
+            (String::from("supermercato"), TransactionCategory::Spesa),
+            (String::from("alimentari"), TransactionCategory::Spesa),
+        ]);
+
+        let transactions = retrieve_transactions(
+            &range,
+            SignConvention::IncomePositive,
+            &AmountColumns::default(),
+            &aliases,
+        )
+        .unwrap();
+        let categories: Vec<TransactionCategory> =
+            transactions.into_iter().map(|t| t.category).collect();
+        assert_eq!(
+            categories,
+            vec![
+                TransactionCategory::Spesa,
+                TransactionCategory::Spesa,
+                TransactionCategory::Affitto
+            ]
+        );
+    }
+
+    #[test]
+    fn text_amounts() {
+        let range = sheet(
+            &[
+                DataType::String(String::from("1.234,56€")),
+                DataType::String(String::from("- 12,50 €")),
+                DataType::Float(-3.0),
+            ]
+            .map(|amount| (amount, DataType::String(String::from("Spesa")))),
+        );
+
+        let transactions = retrieve_transactions(
+            &range,
+            SignConvention::IncomePositive,
+            &AmountColumns::default(),
+            &HashMap::new(),
+        )
+        .unwrap();
+        let amounts: Vec<f32> = transactions.iter().map(|t| t.amount).collect();
+        assert_eq!(amounts, vec![1234.56, -12.5, -3.0]);
+    }
+
+    #[test]
+    fn account_start_date() {
+        let mut workbook: Xlsx<_> =
+            open_workbook("tests/fixtures/registro_ale_account_dates.xlsx").unwrap();
+        let range = workbook.worksheet_range("2023-07").unwrap().unwrap();
+
+        let accounts = retrieve_accounts("2023-07", &range).unwrap();
+        let dates: Vec<NaiveDate> = accounts.iter().map(|a| a.get_initial_date()).collect();
+        assert_eq!(
+            dates,
+            vec![
+                NaiveDate::from_ymd_opt(2023, 7, 15).unwrap(),
+                NaiveDate::from_ymd_opt(2023, 7, 1).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn month_check_warning() {
+        // The logger can be set once, then other tests may have set it
+        let _ = log::set_logger(&WarningLogger);
+        log::set_max_level(LevelFilter::Warn);
+
+        let registry = build_registry(
+            "tests/fixtures/registro_ale_wrong_month.xlsx",
+            "2023-05",
+            SignConvention::IncomePositive,
+            &AmountColumns::default(),
+            MonthCheck::Warn,
+            &HashMap::new(),
+            &MultiProgress::new(),
+        )
+        .unwrap();
+
+        assert_eq!(registry.iter().count(), 2);
+        let warnings = WARNINGS.lock().unwrap();
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("2023-04-04") && w.contains("2023-05")));
+        assert!(!warnings.iter().any(|w| w.contains("2023-05-02")));
+    }
+}
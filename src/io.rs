@@ -4,3 +4,6 @@
 //! and write outputs.
 
 pub mod app_io;
+pub mod categories_config;
+pub mod number_parser;
+pub mod summary;
@@ -1,23 +1,168 @@
 //! Application Input Output
 
+use std::{collections::HashMap, str::FromStr};
+
 use clap::Parser;
 use clap_verbosity_flag::Verbosity;
+use strum_macros::{Display, EnumString};
+
+use crate::{
+    compatibility::{registro_ale::FailedFiles, AmountColumns, CompatibilityEnum, MonthCheck},
+    model::{account::TransactionAccountName, transaction::TransactionCategory},
+    plots::{
+        plot_config::OutputFormat,
+        plot_utils::{
+            palettes::PaletteEnum, resolution::ResolutionEnum, validate_date_format,
+            DEFAULT_DATE_FORMAT,
+        },
+    },
+};
 
-use crate::compatibility::CompatibilityEnum;
+/// Reports that the application can plot
+#[derive(EnumString, Display, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportEnum {
+    /// Daily transactions and their cumulative sum
+    #[strum(serialize = "daily", ascii_case_insensitive)]
+    Daily,
+    /// Pie of the categories of incomes and expenses
+    #[strum(serialize = "pie", ascii_case_insensitive)]
+    Pie,
+    /// Monthly net income, category expenses and pies
+    #[strum(serialize = "monthly", ascii_case_insensitive)]
+    Monthly,
+}
+
+/// Parses the date format of the plots, rejecting the ones chrono cannot format
+fn parse_date_format(date_format: &str) -> Result<String, String> {
+    validate_date_format(date_format)?;
+    Ok(String::from(date_format))
+}
+
+/// Parses an OFX account id and its account written as `ID=account`
+fn parse_ofx_account(value: &str) -> Result<(String, TransactionAccountName), String> {
+    let (id, account) = value
+        .split_once('=')
+        .ok_or_else(|| format!("\"{value}\" is not in the ID=account form"))?;
+    let account = TransactionAccountName::from_str(account.trim())
+        .map_err(|_| format!("\"{account}\" is not an account"))?;
+    Ok((String::from(id.trim()), account))
+}
+
+/// Parses a raw category name and its category written as `name=category`
+fn parse_category_alias(value: &str) -> Result<(String, TransactionCategory), String> {
+    let (name, category) = value
+        .split_once('=')
+        .ok_or_else(|| format!("\"{value}\" is not in the name=category form"))?;
+    let category = TransactionCategory::from_str(category.trim())
+        .map_err(|_| format!("\"{category}\" is not a category"))?;
+    Ok((String::from(name.trim()), category))
+}
 
 /// Arguments to pass to cli application
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct CliArgs {
-    /// The file of the raw file
-    #[arg(short, long)]
-    pub input_file: String,
+    /// The file of the raw file. Repeat it to merge more files, e.g. a workbook
+    /// for each year
+    #[arg(short, long = "input-file", required_unless_present = "input_dir")]
+    pub input_files: Vec<String>,
+    /// Directory whose raw files are merged, sorted by name, after the input files:
+    /// the excel files, or the OFX and QFX ones with the ofx compatibility
+    #[arg(long)]
+    pub input_dir: Option<String>,
+    /// Account of an OFX account id, e.g. IT60X0542811101000000123456=ale. Repeat it
+    /// for more accounts, the ids without it have to be account names
+    #[arg(long = "ofx-account", value_parser = parse_ofx_account)]
+    pub ofx_accounts: Vec<(String, TransactionAccountName)>,
     // Type of compatibility for the input raw file
     #[arg(short, long, default_value_t=CompatibilityEnum::Base)]
     pub compatibility: CompatibilityEnum,
+    /// Negate the amounts of the raw file, for files with positive expenses
+    #[arg(long)]
+    pub invert_amounts: bool,
+    /// Column with the expenses of the raw file, with `--credit-column` it replaces
+    /// the column of the signed amounts
+    #[arg(long, requires = "credit_column")]
+    pub debit_column: Option<String>,
+    /// Column with the incomes of the raw file, see `--debit-column`
+    #[arg(long, requires = "debit_column")]
+    pub credit_column: Option<String>,
+    /// Category of a raw category name of the excel file, e.g. supermercato=spesa.
+    /// Repeat it for more names
+    #[arg(long = "category-alias", value_parser = parse_category_alias)]
+    pub category_aliases: Vec<(String, TransactionCategory)>,
+    /// Check of the transaction dates against the month of their worksheet:
+    /// skip, warn or error to skip the worksheets with dates of other months
+    #[arg(long, default_value_t=MonthCheck::Skip)]
+    pub month_check: MonthCheck,
     /// The folder where to put plots
-    #[arg(short, long)]
-    pub plot_folder: String,
+    #[arg(short, long, required_unless_present_any = ["skip_plots", "accounts_only"])]
+    pub plot_folder: Option<String>,
+    /// Resolution of the plots: 720, 1080, 4k or a custom WIDTHxHEIGHT like 1600x900
+    #[arg(short, long, default_value_t=ResolutionEnum::R720)]
+    pub resolution: ResolutionEnum,
+    /// Text prepended to the title of each plot, e.g. a name or a period
+    #[arg(long, default_value_t = String::new())]
+    pub title_prefix: String,
+    /// Colors of the plots: red or cb-friendly, a colorblind-friendly palette
+    #[arg(long, default_value_t=PaletteEnum::Red)]
+    pub palette: PaletteEnum,
+    /// Plot the daily, pie and monthly reports with a transparent background,
+    /// e.g. to overlay them on slides
+    #[arg(long)]
+    pub transparent_background: bool,
+    /// Image format of the plots: png, jpg or bmp, jpg has no transparent background
+    #[arg(long, default_value_t=OutputFormat::Png)]
+    pub output_format: OutputFormat,
+    /// Json file with the array of the category names in their display order,
+    /// transactions of other categories are reported
+    #[arg(long)]
+    pub categories_config: Option<String>,
+    /// Report to plot: daily, pie or monthly. Repeat it to plot more reports,
+    /// all of them are plotted when it is missing
+    #[arg(long = "report")]
+    pub reports: Vec<ReportEnum>,
+    /// Prefix of the plot file names, to keep the plots of different runs in the same folder
+    #[arg(long)]
+    pub output_prefix: Option<String>,
+    /// Separator between groups of thousands in the plot labels, e.g. '.' for 12.345
+    #[arg(long)]
+    pub thousands_separator: Option<char>,
+    /// Separator of the decimal digits in the plot labels
+    #[arg(long, default_value_t = '.')]
+    pub decimal_separator: char,
+    /// Format of the dates in the time series plots, e.g. "%b %y" for May 23
+    #[arg(
+        long,
+        default_value_t = String::from(DEFAULT_DATE_FORMAT),
+        value_parser = parse_date_format
+    )]
+    pub date_format: String,
+    /// Print a table with the monthly net income and top expense category
+    #[arg(long)]
+    pub summary: bool,
+    /// Json file where to write the totals, the category totals and the monthly net income
+    #[arg(long)]
+    pub summary_json: Option<String>,
+    /// Do not create the plots, e.g. to only print the summary
+    #[arg(long)]
+    pub skip_plots: bool,
+    /// Exit with an error when the extraction of any worksheet fails, instead of
+    /// skipping it with a warning, e.g. in automated pipelines
+    #[arg(long, alias = "fail-on-extraction-error")]
+    pub strict: bool,
+    /// Print the balances of the accounts and exit, without summaries or plots
+    #[arg(long)]
+    pub accounts_only: bool,
+    /// Csv file where to dump the loaded registry before plotting
+    #[arg(long)]
+    pub dump_csv: Option<String>,
+    /// Parquet file where to dump the dataframe of the loaded registry before plotting
+    #[arg(long)]
+    pub dump_dataframe_parquet: Option<String>,
+    /// File where to append the logs, they are still written to stderr
+    #[arg(long)]
+    pub log_file: Option<String>,
     /// Set verbosity level of the application
     ///
     /// -q silences output
@@ -28,3 +173,235 @@ pub struct CliArgs {
     #[command(flatten)]
     pub verbose: Verbosity,
 }
+
+impl CliArgs {
+    /// Returns true if the report has to be plotted
+    pub fn plots_report(&self, report: ReportEnum) -> bool {
+        self.reports.is_empty() || self.reports.contains(&report)
+    }
+
+    /// Returns the columns with the amounts of the raw file
+    pub fn amount_columns(&self) -> AmountColumns {
+        match (&self.debit_column, &self.credit_column) {
+            (Some(debit), Some(credit)) => AmountColumns::DebitCredit {
+                debit: debit.clone(),
+                credit: credit.clone(),
+            },
+            _ => AmountColumns::default(),
+        }
+    }
+
+    /// Returns the accounts of the OFX account ids
+    pub fn ofx_account_ids(&self) -> HashMap<String, TransactionAccountName> {
+        self.ofx_accounts.iter().cloned().collect()
+    }
+
+    /// Returns the categories of the raw category names
+    pub fn category_aliases(&self) -> HashMap<String, TransactionCategory> {
+        self.category_aliases.iter().cloned().collect()
+    }
+
+    /// Returns true if the failed extractions have to stop the application
+    pub fn fails_on_extraction_errors(&self, failed_extractions: &FailedFiles) -> bool {
+        self.strict
+            && failed_extractions
+                .iter()
+                .any(|(_, failed_sheets)| !failed_sheets.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use crate::{
+        compatibility::compatibility_errors::FailedSheet,
+        model::{account::TransactionAccountName, transaction::TransactionCategory},
+    };
+
+    use super::{CliArgs, ReportEnum};
+
+    #[test]
+    fn report_flags() {
+        let args = CliArgs::try_parse_from(["realearning", "-i", "registry.xlsx", "--skip-plots"])
+            .unwrap();
+        assert!(args.reports.is_empty());
+        assert!(args.plots_report(ReportEnum::Daily));
+        assert!(args.plots_report(ReportEnum::Monthly));
+
+        let args = CliArgs::try_parse_from([
+            "realearning",
+            "-i",
+            "registry.xlsx",
+            "--skip-plots",
+            "--report",
+            "pie",
+            "--report",
+            "Monthly",
+        ])
+        .unwrap();
+        assert_eq!(args.reports, vec![ReportEnum::Pie, ReportEnum::Monthly]);
+        assert!(!args.plots_report(ReportEnum::Daily));
+        assert!(args.plots_report(ReportEnum::Pie));
+
+        assert!(CliArgs::try_parse_from([
+            "realearning",
+            "-i",
+            "registry.xlsx",
+            "--skip-plots",
+            "--report",
+            "weekly",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn input_files() {
+        let args = CliArgs::try_parse_from([
+            "realearning",
+            "-i",
+            "2022.xlsx",
+            "--input-file",
+            "2023.xlsx",
+            "--skip-plots",
+        ])
+        .unwrap();
+        assert_eq!(args.input_files, vec!["2022.xlsx", "2023.xlsx"]);
+
+        assert!(CliArgs::try_parse_from(["realearning", "--skip-plots"]).is_err());
+        let args =
+            CliArgs::try_parse_from(["realearning", "--input-dir", "registries", "--skip-plots"])
+                .unwrap();
+        assert!(args.input_files.is_empty());
+        assert_eq!(args.input_dir.as_deref(), Some("registries"));
+    }
+
+    #[test]
+    fn accounts_only() {
+        let args =
+            CliArgs::try_parse_from(["realearning", "-i", "registry.xlsx", "--accounts-only"])
+                .unwrap();
+        assert!(args.accounts_only);
+        assert_eq!(args.plot_folder, None);
+
+        let args =
+            CliArgs::try_parse_from(["realearning", "-i", "registry.xlsx", "-p", "plots"]).unwrap();
+        assert!(!args.accounts_only);
+        assert!(CliArgs::try_parse_from(["realearning", "-i", "registry.xlsx"]).is_err());
+    }
+
+    #[test]
+    fn ofx_accounts() {
+        let args = CliArgs::try_parse_from([
+            "realearning",
+            "-i",
+            "statement.ofx",
+            "--skip-plots",
+            "--ofx-account",
+            "IT60X0542811101000000123456=ale",
+            "--ofx-account",
+            "4321=carta giulia",
+        ])
+        .unwrap();
+        let account_ids = args.ofx_account_ids();
+        assert_eq!(account_ids.len(), 2);
+        assert_eq!(
+            account_ids["IT60X0542811101000000123456"],
+            TransactionAccountName::Ale
+        );
+        assert_eq!(account_ids["4321"], TransactionAccountName::CartaGiulia);
+
+        for value in ["4321", "4321=bank"] {
+            assert!(CliArgs::try_parse_from([
+                "realearning",
+                "-i",
+                "statement.ofx",
+                "--skip-plots",
+                "--ofx-account",
+                value,
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn category_aliases() {
+        let args = CliArgs::try_parse_from([
+            "realearning",
+            "-i",
+            "2023.xlsx",
+            "--skip-plots",
+            "--category-alias",
+            "supermercato=spesa",
+            "--category-alias",
+            "mensa = pranzo lavoro",
+        ])
+        .unwrap();
+        let aliases = args.category_aliases();
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(aliases["supermercato"], TransactionCategory::Spesa);
+        assert_eq!(aliases["mensa"], TransactionCategory::PranzoLavoro);
+
+        for value in ["supermercato", "supermercato=negozio"] {
+            assert!(CliArgs::try_parse_from([
+                "realearning",
+                "-i",
+                "2023.xlsx",
+                "--skip-plots",
+                "--category-alias",
+                value,
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn date_format() {
+        let args = CliArgs::try_parse_from([
+            "realearning",
+            "-i",
+            "2023.xlsx",
+            "--skip-plots",
+            "--date-format",
+            "%b %y",
+        ])
+        .unwrap();
+        assert_eq!(args.date_format, "%b %y");
+
+        assert!(CliArgs::try_parse_from([
+            "realearning",
+            "-i",
+            "2023.xlsx",
+            "--skip-plots",
+            "--date-format",
+            "%Q",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn strict_extraction() {
+        let failed_sheet = FailedSheet {
+            name: String::from("2023-05"),
+            reason: String::from("invalid data"),
+        };
+        let failed = vec![
+            (String::from("2022.xlsx"), vec![]),
+            (String::from("2023.xlsx"), vec![failed_sheet]),
+        ];
+        let succeeded = vec![(String::from("2022.xlsx"), vec![])];
+
+        let args =
+            CliArgs::try_parse_from(["realearning", "-i", "2023.xlsx", "--skip-plots"]).unwrap();
+        assert!(!args.strict);
+        assert!(!args.fails_on_extraction_errors(&failed));
+
+        for flag in ["--strict", "--fail-on-extraction-error"] {
+            let args =
+                CliArgs::try_parse_from(["realearning", "-i", "2023.xlsx", "--skip-plots", flag])
+                    .unwrap();
+            assert!(args.fails_on_extraction_errors(&failed));
+            assert!(!args.fails_on_extraction_errors(&succeeded));
+        }
+    }
+}
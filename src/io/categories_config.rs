@@ -0,0 +1,69 @@
+//! Categories Configuration
+//!
+//! Json file listing the categories of the registry in their display order, e.g.
+//! `["Stipendio", "Affitto", "Spesa"]`. The names are the ones of the worksheets.
+
+use std::{error, fmt, fs, str::FromStr};
+
+use crate::model::{
+    registry::Registry,
+    transaction::{TransactionCategory, TransactionEvent},
+};
+
+/// A categories configuration that cannot be used
+#[derive(Debug, Clone, PartialEq)]
+pub enum CategoriesConfigError {
+    /// The name is not a known category
+    UnknownCategory(String),
+    /// The category is listed more than once
+    DuplicateCategory(String),
+}
+
+impl fmt::Display for CategoriesConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CategoriesConfigError::UnknownCategory(name) => write!(f, "unknown category {}", name),
+            CategoriesConfigError::DuplicateCategory(name) => {
+                write!(f, "category {} is listed more than once", name)
+            }
+        }
+    }
+}
+
+impl error::Error for CategoriesConfigError {}
+
+/// Categories expected in the registry in their display order
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoriesConfig {
+    pub categories: Vec<TransactionCategory>,
+}
+
+impl CategoriesConfig {
+    /// Parse the configuration from a json array of category names
+    pub fn from_json(json: &str) -> Result<CategoriesConfig, Box<dyn error::Error>> {
+        let names: Vec<String> = serde_json::from_str(json)?;
+        let mut categories: Vec<TransactionCategory> = Vec::with_capacity(names.len());
+        for name in names {
+            let category = TransactionCategory::from_str(&name)
+                .map_err(|_| CategoriesConfigError::UnknownCategory(name.clone()))?;
+            if categories.contains(&category) {
+                return Err(Box::new(CategoriesConfigError::DuplicateCategory(name)));
+            }
+            categories.push(category);
+        }
+        Ok(CategoriesConfig { categories })
+    }
+
+    /// Read the configuration from a json file
+    pub fn from_file(path: &str) -> Result<CategoriesConfig, Box<dyn error::Error>> {
+        CategoriesConfig::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Returns the transactions of the registry whose category is not in the configuration
+    pub fn unknown_transactions<'a>(&self, registry: &'a Registry) -> Vec<&'a TransactionEvent> {
+        registry
+            .iter()
+            .filter(|t| !self.categories.contains(&t.category))
+            .collect()
+    }
+}
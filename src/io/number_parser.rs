@@ -0,0 +1,116 @@
+//! Number parser
+//!
+//! Parses the amounts written as text in the raw files, e.g. "1.234,56€".
+
+use serde::{Deserialize, Deserializer};
+
+/// Currency symbols removed from the amounts
+const CURRENCY_SYMBOLS: [char; 4] = ['€', '$', '£', '¥'];
+
+/// Separators of the numbers written as text
+///
+/// The default is the European format with `.` between the thousands and `,`
+/// before the decimal digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberParser {
+    pub thousands_separator: char,
+    pub decimal_separator: char,
+}
+
+impl Default for NumberParser {
+    fn default() -> Self {
+        NumberParser::EUROPEAN
+    }
+}
+
+impl NumberParser {
+    /// Numbers like 1.234,56
+    pub const EUROPEAN: NumberParser = NumberParser {
+        thousands_separator: '.',
+        decimal_separator: ',',
+    };
+    /// Numbers like 1,234.56
+    pub const US: NumberParser = NumberParser {
+        thousands_separator: ',',
+        decimal_separator: '.',
+    };
+
+    /// Parses an amount written as text
+    ///
+    /// Whitespaces, currency symbols and currency codes like EUR are removed, then
+    /// the thousands separators are dropped. Returns `None` when the remaining text
+    /// is not a number.
+    pub fn parse(&self, text: &str) -> Option<f32> {
+        let cleaned: String = text
+            .chars()
+            .filter(|c| !c.is_whitespace() && !CURRENCY_SYMBOLS.contains(c))
+            .collect();
+        let number: String = cleaned
+            .trim_matches(|c: char| c.is_ascii_alphabetic())
+            .chars()
+            .filter(|c| *c != self.thousands_separator)
+            .map(|c| if c == self.decimal_separator { '.' } else { c })
+            .collect();
+        number.parse().ok()
+    }
+}
+
+/// Amount of a raw file, either a number or a text
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawAmount {
+    Number(f32),
+    Text(String),
+}
+
+/// Deserializes an amount that can be written as text
+///
+/// Numbers are kept as they are, texts are parsed with the default `NumberParser`.
+pub fn deserialize_amount<'de, D>(deserializer: D) -> Result<f32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match RawAmount::deserialize(deserializer)? {
+        RawAmount::Number(amount) => Ok(amount),
+        RawAmount::Text(text) => NumberParser::default()
+            .parse(&text)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid amount {text}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NumberParser;
+
+    #[test]
+    fn european_amounts() {
+        let parser = NumberParser::EUROPEAN;
+        for (text, expected) in [
+            ("1.234,56€", 1234.56),
+            ("-1.234,56 €", -1234.56),
+            ("€ 12,5", 12.5),
+            ("1.000.000", 1000000.0),
+            ("EUR 42", 42.0),
+            ("0,01", 0.01),
+        ] {
+            assert_eq!(parser.parse(text), Some(expected), "{text}");
+        }
+        assert_eq!(parser.parse("12,34,56"), None);
+        assert_eq!(parser.parse("abc"), None);
+        assert_eq!(parser.parse(""), None);
+    }
+
+    #[test]
+    fn us_amounts() {
+        let parser = NumberParser::US;
+        for (text, expected) in [
+            ("$1,234.56", 1234.56),
+            ("-$ 1,234.56", -1234.56),
+            ("1,000,000", 1000000.0),
+            ("42 USD", 42.0),
+        ] {
+            assert_eq!(parser.parse(text), Some(expected), "{text}");
+        }
+        assert_eq!(parser.parse("1.234.56"), None);
+    }
+}
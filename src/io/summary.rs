@@ -0,0 +1,138 @@
+//! Text Summary
+//!
+//! Tables of the registry extractions printed to the terminal instead of plots,
+//! and aggregates of the registry written to json.
+
+use std::{collections::BTreeMap, fmt::Write, fs::File};
+
+use serde::Serialize;
+
+use crate::{
+    model::{
+        registry::{decimal_f64, serialized_name, Registry, AMOUNT_COLUMN},
+        transaction::Flow,
+    },
+    plots::{
+        extraction::{extract_net_by_period, MonthlyTransactions, NetByPeriod, Period},
+        plot_utils::NumberFormat,
+    },
+};
+
+const MONTH_HEADER: &str = "Month";
+const NET_INCOME_HEADER: &str = "Net income";
+const TOP_EXPENSE_HEADER: &str = "Top expense category";
+
+/// Formats the monthly transactions as a text table
+///
+/// Each row has the month, its net income and the category with the largest expenses
+/// of the month together with its amount. Months without expenses have an empty
+/// top expense category.
+pub fn monthly_summary(monthly: &MonthlyTransactions, number_format: &NumberFormat) -> String {
+    let rows: Vec<(String, String, String)> = monthly
+        .months
+        .iter()
+        .enumerate()
+        .map(|(i, month)| {
+            let top_expense = monthly
+                .categories_amounts_perc_names
+                .get(i)
+                .and_then(|names| names.first())
+                .zip(
+                    monthly
+                        .categories_amounts_perc_value
+                        .get(i)
+                        .and_then(|amounts| amounts.first()),
+                )
+                .map(|(category, amount)| {
+                    format!("{} ({})", category, number_format.format(*amount, 2))
+                })
+                .unwrap_or_default();
+            (
+                month.format("%Y-%m").to_string(),
+                number_format.format(monthly.net_income[i], 2),
+                top_expense,
+            )
+        })
+        .collect();
+
+    let month_width = rows
+        .iter()
+        .map(|row| row.0.chars().count())
+        .chain([MONTH_HEADER.len()])
+        .max()
+        .unwrap_or_default();
+    let net_income_width = rows
+        .iter()
+        .map(|row| row.1.chars().count())
+        .chain([NET_INCOME_HEADER.len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut summary = String::new();
+    let _ = writeln!(
+        summary,
+        "{:<month_width$}  {:>net_income_width$}  {}",
+        MONTH_HEADER, NET_INCOME_HEADER, TOP_EXPENSE_HEADER
+    );
+    for (month, net_income, top_expense) in rows {
+        let _ = writeln!(
+            summary,
+            "{:<month_width$}  {:>net_income_width$}  {}",
+            month, net_income, top_expense
+        );
+    }
+    summary
+}
+
+/// Aggregates of the registry for other applications, like dashboards
+#[derive(Serialize)]
+pub struct RegistrySummary {
+    /// Sum of the incomes
+    pub total_income: f64,
+    /// Sum of the expenses, as a negative amount
+    pub total_expense: f64,
+    pub net: f64,
+    /// Net amount of each category by its name
+    pub category_totals: BTreeMap<String, f64>,
+    pub monthly_net_income: NetByPeriod,
+}
+
+impl RegistrySummary {
+    /// Computes the summary of the registry
+    ///
+    /// An empty registry is a `PlotError::NoData` like the extractions.
+    pub fn new(registry: &Registry) -> Result<RegistrySummary, Box<dyn std::error::Error>> {
+        let monthly_net_income = extract_net_by_period(registry, Period::Month, None, None)?;
+
+        let df = registry.to_dataframe()?;
+        let mut total_income = 0.0;
+        let mut total_expense = 0.0;
+        for amount in df.column(AMOUNT_COLUMN)?.f64()?.into_iter().flatten() {
+            match Flow::of(amount) {
+                Flow::Income => total_income += amount,
+                Flow::Expense => total_expense += amount,
+                Flow::Zero => {}
+            }
+        }
+
+        let category_totals = registry
+            .category_totals(None)
+            .into_iter()
+            .map(|(category, total)| Ok((serialized_name(&category)?, decimal_f64(total))))
+            .collect::<Result<BTreeMap<String, f64>, serde_json::Error>>()?;
+
+        Ok(RegistrySummary {
+            total_income,
+            total_expense,
+            net: total_income + total_expense,
+            category_totals,
+            monthly_net_income,
+        })
+    }
+
+    /// Writes the summary as json to the file in `path`, replacing its content
+    pub fn to_json_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        serde_json::to_writer_pretty(File::create(path)?, self)?;
+        Ok(())
+    }
+}
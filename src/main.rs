@@ -1,88 +1,376 @@
-use std::{fs::DirBuilder, path::Path, process};
+use std::{
+    fs::{DirBuilder, File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    process,
+};
 
 use clap::Parser;
-use log::{error, info, warn};
+use env_logger::Target;
+use log::{debug, error, info, warn};
 use realearning::{
-    compatibility::{registro_ale::build_registry_batch, CompatibilityEnum},
-    io::app_io::CliArgs,
+    compatibility::{
+        input_paths, ofx, registro_ale::build_registry_files, CompatibilityEnum, SignConvention,
+    },
+    io::{
+        app_io::{CliArgs, ReportEnum},
+        categories_config::CategoriesConfig,
+        summary::{monthly_summary, RegistrySummary},
+    },
+    model::registry::Registry,
     plots::{
-        plot_registry::*,
-        plot_utils::{palettes::RED_PALETTE, resolution::R720},
+        extraction::monthy_extraction, plot_config::PlotConfig, plot_errors::PlotError,
+        plot_registry::*, plot_utils::NumberFormat,
     },
 };
 use regex::Regex;
 
+/// Writer that copies the logs to stderr and to a file
+struct TeeWriter {
+    file: File,
+}
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write_all(buf)?;
+        self.file.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()?;
+        self.file.flush()
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = CliArgs::parse();
-    env_logger::Builder::new()
-        .filter_level(args.verbose.log_level_filter())
-        .init();
+    let mut logger = env_logger::Builder::new();
+    logger.filter_level(args.verbose.log_level_filter());
+    if let Some(log_file) = &args.log_file {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_file)
+            .unwrap_or_else(|e| {
+                // The logger is not initialized yet
+                eprintln!("Failed to open log file {} with error \"{}\"", log_file, e);
+                process::exit(1)
+            });
+        logger.target(Target::Pipe(Box::new(TeeWriter { file })));
+    }
+    logger.init();
+
+    let categories_config = args.categories_config.as_ref().map(|path| {
+        CategoriesConfig::from_file(path)
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!(
+                        "Failed to read the categories config {} with error \"{}\"",
+                        path, e
+                    )
+                );
+                process::exit(1)
+            })
+            .unwrap()
+    });
+    let category_order = categories_config
+        .as_ref()
+        .map(|config| config.categories.as_slice());
 
     let re = Regex::new(r"^\d{4}-\d{2}$").unwrap();
+    let sign_convention = if args.invert_amounts {
+        SignConvention::IncomeNegative
+    } else {
+        SignConvention::IncomePositive
+    };
 
-    match args.compatibility {
+    let mut input_files = args.input_files.clone();
+    if let Some(input_dir) = &args.input_dir {
+        let dir_files = input_paths(input_dir, args.compatibility.file_extensions())
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!(
+                        "Failed to read the directory {} with error \"{}\"",
+                        input_dir, e
+                    )
+                );
+                process::exit(1)
+            })
+            .unwrap();
+        input_files.extend(dir_files);
+    }
+
+    let loaded_registry = match args.compatibility {
         CompatibilityEnum::Ale => {
-            let (loaded_registry, failed_extractions) = build_registry_batch(&args.input_file, re)
-                .map_err(|e| {
-                    error!(
-                        "{}",
-                        format!(
-                            "Failed to extract registry from {} with error \"{}\"",
-                            args.input_file, e
-                        )
+            let (loaded_registry, failed_extractions) = build_registry_files(
+                &input_files,
+                re,
+                sign_convention,
+                &args.amount_columns(),
+                args.month_check,
+                &args.category_aliases(),
+            )
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!("Failed to extract registry with error \"{}\"", e)
+                );
+                process::exit(1)
+            })
+            .unwrap();
+
+            for (input_file, failed_sheets) in &failed_extractions {
+                for failed_sheet in failed_sheets {
+                    warn!(
+                        "Failed extraction of worksheet {} of {}",
+                        failed_sheet, input_file
                     );
-                    process::exit(1)
-                })
-                .unwrap();
+                }
+            }
+            if args.fails_on_extraction_errors(&failed_extractions) {
+                error!("Stopping because of the failed extractions in strict mode");
+                process::exit(1)
+            }
+            loaded_registry
+        }
+        CompatibilityEnum::Ofx => {
+            let account_ids = args.ofx_account_ids();
+            let mut loaded_registry = Registry::new(None);
+            for input_file in &input_files {
+                let registry = ofx::build_registry(input_file, &account_ids)
+                    .map_err(|e| {
+                        error!(
+                            "{}",
+                            format!(
+                                "Failed to extract registry from {} with error \"{}\"",
+                                input_file, e
+                            )
+                        );
+                        process::exit(1)
+                    })
+                    .unwrap();
+                loaded_registry.extend(registry);
+            }
+            loaded_registry
+        }
+        _ => {
+            error!("Only implemented compatibilities are Ale and Ofx");
+            return Ok(());
+        }
+    };
 
-            if !failed_extractions.is_empty() {
-                warn!("Failed Extractions {:?}", failed_extractions);
+    if args.accounts_only {
+        print!("{}", loaded_registry);
+        return Ok(());
+    }
+
+    let sign_anomalies = loaded_registry.sign_anomalies();
+    if !sign_anomalies.is_empty() {
+        warn!(
+            "{} transactions have an amount sign unexpected for their category",
+            sign_anomalies.len()
+        );
+        for transaction in sign_anomalies {
+            debug!("Unexpected sign: {}", transaction);
+        }
+    }
+    if let Some(config) = &categories_config {
+        let unknown_transactions = config.unknown_transactions(&loaded_registry);
+        if !unknown_transactions.is_empty() {
+            warn!(
+                "{} transactions have a category missing from the categories config",
+                unknown_transactions.len()
+            );
+            for transaction in unknown_transactions {
+                debug!("Unknown category: {}", transaction);
             }
-            let df = loaded_registry
-                .to_dataframe()
-                .map_err(|e| {
-                    error!(
-                        "{}",
-                        format!(
-                            "Failed to transform the registry to dataframe with error \"{}\"",
-                            e
-                        )
+        }
+    }
+    if let Some(csv_path) = &args.dump_csv {
+        loaded_registry
+            .to_csv(csv_path)
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!(
+                        "Failed to dump the registry to {} with error \"{}\"",
+                        csv_path, e
                     )
-                })
-                .unwrap();
-            info!("The registry has shape {:?}", df.shape());
+                );
+                process::exit(1)
+            })
+            .unwrap();
+        info!(
+            "Written {} transactions to {}",
+            loaded_registry.len(),
+            csv_path
+        );
+    }
+    if let Some(parquet_path) = &args.dump_dataframe_parquet {
+        loaded_registry
+            .to_parquet(parquet_path)
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!(
+                        "Failed to dump the dataframe to {} with error \"{}\"",
+                        parquet_path, e
+                    )
+                );
+                process::exit(1)
+            })
+            .unwrap();
+        info!("Written the registry dataframe to {}", parquet_path);
+    }
+    let df = loaded_registry
+        .to_dataframe()
+        .map_err(|e| {
+            error!(
+                "{}",
+                format!(
+                    "Failed to transform the registry to dataframe with error \"{}\"",
+                    e
+                )
+            )
+        })
+        .unwrap();
+    info!("The registry has shape {:?}", df.shape());
+    match loaded_registry.date_range() {
+        Some((from, to)) => info!("The registry spans from {} to {}", from, to),
+        None => warn!("The registry has no transactions, the plots are skipped"),
+    }
 
-            if !Path::new(&args.plot_folder).is_dir() {
-                DirBuilder::new()
-                    .create(&args.plot_folder)
+    let number_format = NumberFormat {
+        thousands_separator: args.thousands_separator,
+        decimal_separator: args.decimal_separator,
+    };
+    if args.summary {
+        match monthy_extraction(
+            &loaded_registry,
+            None,
+            None,
+            None,
+            None,
+            None,
+            category_order,
+        ) {
+            Ok(monthly) => print!("{}", monthly_summary(&monthly, &number_format)),
+            Err(e) => warn!("Skipping monthly summary: {}", e),
+        }
+    }
+    if let Some(json_path) = &args.summary_json {
+        match RegistrySummary::new(&loaded_registry) {
+            Ok(summary) => {
+                summary
+                    .to_json_file(json_path)
                     .map_err(|e| {
                         error!(
                             "{}",
                             format!(
-                                "Failed to create plot directory {} with error \"{}\"",
-                                args.plot_folder, e
+                                "Failed to write the summary to {} with error \"{}\"",
+                                json_path, e
                             )
                         );
                         process::exit(1)
                     })
                     .unwrap();
+                info!("Written the summary to {}", json_path);
             }
-            plot_daily_transactions(&loaded_registry, R720, &args.plot_folder, &RED_PALETTE)
+            Err(e) => warn!("Skipping json summary: {}", e),
+        }
+    }
+
+    // The plots are bounded by the dates of the registry, an empty registry has
+    // no dates to bound them, then they are skipped
+    if let Some(plot_folder) = args
+        .plot_folder
+        .as_ref()
+        .filter(|_| !args.skip_plots && !loaded_registry.is_empty())
+    {
+        if !Path::new(plot_folder).is_dir() {
+            DirBuilder::new()
+                .create(plot_folder)
+                .map_err(|e| {
+                    error!(
+                        "{}",
+                        format!(
+                            "Failed to create plot directory {} with error \"{}\"",
+                            plot_folder, e
+                        )
+                    );
+                    process::exit(1)
+                })
                 .unwrap();
-            plot_category_pie(&loaded_registry, R720, 7, &args.plot_folder, &RED_PALETTE).unwrap();
-            plot_monthly_report(
-                &loaded_registry,
-                R720,
-                Some(10),
-                &args.plot_folder,
-                &RED_PALETTE,
-            )
-            .unwrap();
         }
-        _ => {
-            error!("Only implemented compatibility is Ale");
+        let config = PlotConfig::new(plot_folder)
+            .prefix(args.output_prefix.as_deref())
+            .title_prefix(&args.title_prefix)
+            .resolution(args.resolution.size())
+            .palette(args.palette.palette().clone())
+            .output_format(args.output_format)
+            .transparent_background(args.transparent_background)
+            .number_format(number_format)
+            .date_format(&args.date_format)
+            .date_range(loaded_registry.date_range());
+        if args.plots_report(ReportEnum::Daily) {
+            handle_plot_result(
+                "daily transactions",
+                plot_daily_transactions(&loaded_registry, &config, true, None),
+            );
         }
-    };
+        if args.plots_report(ReportEnum::Pie) {
+            handle_plot_result(
+                "category pie",
+                plot_category_pie(
+                    &loaded_registry,
+                    &config,
+                    7,
+                    PieMode::Both,
+                    SliceOrder::BySize,
+                    PieLabel::Percentage,
+                    &PieStyle::default(),
+                ),
+            );
+        }
+        if args.plots_report(ReportEnum::Monthly) {
+            handle_plot_result(
+                "monthly report",
+                plot_monthly_report(
+                    &loaded_registry,
+                    &config,
+                    Some(10),
+                    PieLabel::Both,
+                    &PieStyle::default(),
+                    3,
+                    false,
+                    category_order,
+                ),
+            );
+        }
+    }
 
     Ok(())
 }
+
+/// Logs the result of a plot
+///
+/// Plots without data or with invalid parameters are skipped with a warning,
+/// any other error stops the application.
+fn handle_plot_result(name: &str, result: Result<(), Box<dyn std::error::Error>>) {
+    match result {
+        Ok(()) => info!("Created {} plot", name),
+        Err(e) if e.downcast_ref::<PlotError>().is_some() => {
+            warn!("Skipping {} plot: {}", name, e)
+        }
+        Err(e) => {
+            error!(
+                "{}",
+                format!("Failed to create {} plot with error \"{}\"", name, e)
+            );
+            process::exit(1)
+        }
+    }
+}
@@ -4,11 +4,11 @@
 
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
-use std::ops::Add;
+use std::{ops::Add, str::FromStr};
 use strum_macros::{Display, EnumString};
 
 /// TransactionSource enum with possible account of transactions.
-#[derive(EnumString, Display, Serialize, Deserialize, PartialEq, Clone)]
+#[derive(EnumString, Display, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub enum TransactionAccountName {
     #[strum(ascii_case_insensitive)]
     Ale,
@@ -24,14 +24,59 @@ pub enum TransactionAccountName {
     Giulia,
 }
 
+impl TransactionAccountName {
+    /// Returns the human readable name of the account shown by the plots and the
+    /// printed registry
+    ///
+    /// `Display` and `FromStr` keep the identifier written in the raw files,
+    /// e.g. "carta ale" is displayed as "Carta di Credito Ale".
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TransactionAccountName::Ale => "Ale",
+            TransactionAccountName::BuonoPasto => "Buono Pasto",
+            TransactionAccountName::CartaAle => "Carta di Credito Ale",
+            TransactionAccountName::CartaGiulia => "Carta di Credito Giulia",
+            TransactionAccountName::Contante => "Contante",
+            TransactionAccountName::Giulia => "Giulia",
+        }
+    }
+
+    /// Returns the account written either with its identifier or with its
+    /// serialized name, e.g. "carta ale" or "CartaAle"
+    pub fn from_name(name: &str) -> Option<TransactionAccountName> {
+        TransactionAccountName::from_str(name)
+            .ok()
+            .or_else(|| serde_json::from_value(serde_json::Value::from(name)).ok())
+    }
+
+    /// Returns the display name of an account written either with its identifier
+    /// or with its serialized name, e.g. "carta ale" or "CartaAle"
+    ///
+    /// Unknown names are returned as they are.
+    pub fn display_name_of(name: &str) -> String {
+        TransactionAccountName::from_name(name).map_or_else(
+            || String::from(name),
+            |account| String::from(account.display_name()),
+        )
+    }
+}
+
 /// Bank account with name and value
 ///
 /// An account has a `name`, a `current_value` and `history` of values with timestamps
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct Account {
     pub name: TransactionAccountName,
     pub current_value: f32,
     history: Vec<(NaiveDate, f32)>,
+    /// Date and value of the account before any transaction, the value is zero
+    /// when the account has been created by its first transaction
+    #[serde(skip)]
+    opening: (NaiveDate, f32),
+    /// True when the account has been created by its first transaction,
+    /// then its initial value already includes that transaction
+    #[serde(skip)]
+    opened_by_transaction: bool,
 }
 
 impl Account {
@@ -46,6 +91,23 @@ impl Account {
             name,
             current_value: value,
             history: vec![(date, value)],
+            opening: (date, value),
+            opened_by_transaction: false,
+        }
+    }
+
+    /// Create a new account from its first transaction
+    ///
+    /// The initial value of the account is the amount of the transaction
+    pub(crate) fn from_transaction(
+        name: TransactionAccountName,
+        amount: f32,
+        date: NaiveDate,
+    ) -> Account {
+        Account {
+            opening: (date, 0.0),
+            opened_by_transaction: true,
+            ..Account::new(name, amount, date)
         }
     }
 
@@ -64,7 +126,41 @@ impl Account {
 
     /// Get the earlier value of the account
     pub fn get_initial_value(&self) -> f32 {
-        self.history.iter().min_by_key(|&(date, _)| date).unwrap().1
+        self.history
+            .iter()
+            .min_by_key(|&(date, _)| date)
+            .map_or(self.opening.1, |&(_, value)| value)
+    }
+
+    /// Get the date of the earlier value of the account
+    pub fn get_initial_date(&self) -> NaiveDate {
+        self.history
+            .iter()
+            .min_by_key(|&(date, _)| date)
+            .map_or(self.opening.0, |&(date, _)| date)
+    }
+
+    /// Returns the date and value of the account before any transaction
+    pub(crate) fn get_opening(&self) -> (NaiveDate, f32) {
+        self.opening
+    }
+
+    /// Returns true when the initial value includes the first transaction of the account
+    pub(crate) fn is_opened_by_transaction(&self) -> bool {
+        self.opened_by_transaction
+    }
+
+    /// Resets the account to its opening value dropping the history of the transactions
+    ///
+    /// An account opened by a transaction is left without history, its transactions
+    /// have to be replayed from a zero value.
+    pub(crate) fn reset(&mut self) {
+        self.history = if self.opened_by_transaction {
+            Vec::new()
+        } else {
+            vec![self.opening]
+        };
+        self.current_value = self.opening.1;
     }
 }
 
@@ -81,6 +177,13 @@ impl Add for Account {
         if self.name != other.name {
             panic!("The accounts must have the same name!");
         } else {
+            // The merged account starts as the one with the earlier initial value
+            let (opening, opened_by_transaction) =
+                if self.get_initial_date() <= other.get_initial_date() {
+                    (self.opening, self.opened_by_transaction)
+                } else {
+                    (other.opening, other.opened_by_transaction)
+                };
             // Merge the history of the two accounts and take as current value the newest one
             let mut new_history: Vec<(NaiveDate, f32)> = Vec::new();
             new_history.extend(self.history);
@@ -91,6 +194,8 @@ impl Add for Account {
                 name: self.name,
                 current_value,
                 history: new_history,
+                opening,
+                opened_by_transaction,
             }
         }
     }
@@ -3,9 +3,16 @@
 //! A Registry is composed of a set of accounts and transactions over them
 //!
 
-use super::{account::Account, transaction::TransactionEvent};
+use super::{
+    account::{Account, TransactionAccountName},
+    transaction::{Sign, TransactionCategory, TransactionEvent},
+};
+use chrono::NaiveDate;
 use csv;
-use polars::prelude::*;
+use polars::prelude::{
+    col, lit, DataFrame, DataType, Field, IntoLazy, JsonReader, NamedFrom, ParquetWriter, Schema,
+    SerReader, Series, StrpTimeOptions,
+};
 use serde::Serialize;
 use std::{
     collections::HashMap,
@@ -13,8 +20,75 @@ use std::{
     fs::{File, OpenOptions},
     io::{self, Cursor},
     ops::Add,
+    str::FromStr,
 };
 
+/// Returns the name of an enum variant in the serialized transactions
+pub(crate) fn serialized_name<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(name) => Ok(name),
+        other => Err(serde::ser::Error::custom(format!(
+            "{other} is not the name of a variant"
+        ))),
+    }
+}
+
+/// Returns the serialized names of the accounts written either with their identifier
+/// or with their serialized name, unknown names are kept as they are
+fn serialized_account_names(names: &[String]) -> Result<Vec<String>, serde_json::Error> {
+    names
+        .iter()
+        .map(|name| match TransactionAccountName::from_name(name) {
+            Some(account) => serialized_name(&account),
+            None => Ok(name.clone()),
+        })
+        .collect()
+}
+
+/// Converts an amount to `f64` keeping its shortest decimal representation
+///
+/// A plain cast keeps the binary error of the `f32`, e.g. 0.01 becomes 0.009999999776.
+pub(crate) fn decimal_f64(amount: f32) -> f64 {
+    amount
+        .to_string()
+        .parse()
+        .unwrap_or_else(|_| f64::from(amount))
+}
+
+/// Name of the date column of the registry dataframe
+pub const DATE_COLUMN: &str = "date";
+/// Name of the amount column of the registry dataframe
+pub const AMOUNT_COLUMN: &str = "amount";
+/// Name of the category column of the registry dataframe
+pub const CATEGORY_COLUMN: &str = "category";
+/// Name of the description column of the registry dataframe
+pub const DESCRIPTION_COLUMN: &str = "description";
+/// Name of the account column of the registry dataframe
+pub const ACCOUNT_COLUMN: &str = "account";
+
+/// Maximum number of transactions printed by the `Display` of the registry
+const DISPLAYED_TRANSACTIONS: usize = 5;
+
+/// Difference between the expected balance of an account and the one computed
+/// from its transactions
+#[derive(Debug, PartialEq)]
+pub struct BalanceDiscrepancy {
+    pub account: String,
+    pub expected: f32,
+    /// Balance computed by the registry, `None` if the account is missing
+    pub computed: Option<f32>,
+}
+
+/// Values and activity of an account of the registry
+#[derive(Debug, PartialEq)]
+pub struct AccountSummary {
+    pub name: String,
+    pub current_value: f32,
+    pub initial_value: f32,
+    /// Number of transactions of the account
+    pub transactions: usize,
+}
+
 /// Registry that contains a set of transactions
 #[derive(Serialize)]
 pub struct Registry {
@@ -38,15 +112,31 @@ impl Registry {
         }
     }
 
+    /// Create a registry with accounts and their transactions in a single call
+    ///
+    /// Transactions are sorted by date and the balances of the accounts are updated
+    /// as with `new` followed by `add_batch`.
+    pub fn from_transactions(
+        transactions: Vec<TransactionEvent>,
+        accounts: Option<Vec<Account>>,
+    ) -> Registry {
+        let mut registry = Registry::new(accounts);
+        registry.add_batch(transactions);
+        registry
+    }
+
     /// Add a transaction to the registry
     ///
     /// If the account of the transaction is not already present then it is added
     /// to the account list. If the account already exists then its value is updated
+    ///
+    /// Transactions are kept sorted by date, the ones on the same date
+    /// keep their insertion order.
     pub fn add_single(&mut self, transaction: TransactionEvent) {
         if let std::collections::hash_map::Entry::Vacant(e) =
             self.accounts.entry(transaction.account.to_string())
         {
-            e.insert(Account::new(
+            e.insert(Account::from_transaction(
                 transaction.account.clone(),
                 transaction.amount,
                 transaction.date,
@@ -58,23 +148,322 @@ impl Registry {
                 .unwrap();
             account.set_value(account.current_value + transaction.amount, transaction.date)
         }
-        self.transactions.push(transaction);
+        let position = self
+            .transactions
+            .partition_point(|t| t.date <= transaction.date);
+        self.transactions.insert(position, transaction);
     }
 
     /// Add a batch of transactions to the registry
     pub fn add_batch(&mut self, transactions: Vec<TransactionEvent>) {
         let mut transactions = transactions;
+        // the sort is stable so that transactions on the same date keep their order
         transactions.sort_by_key(|t| t.date);
         for transaction in transactions {
             self.add_single(transaction);
         }
     }
 
+    /// Merge another registry into this one
+    ///
+    /// Transactions are merged keeping the date order, with the transactions of
+    /// `other` after the ones of the same date already in the registry.
+    /// Accounts with the same name are summed.
+    pub fn extend(&mut self, other: Registry) {
+        self.transactions.extend(other.transactions);
+        self.transactions.sort_by_key(|t| t.date);
+
+        for (name, other_account) in other.accounts {
+            let account = match self.accounts.remove(&name) {
+                Some(account) => account + other_account,
+                None => other_account,
+            };
+            self.accounts.insert(name, account);
+        }
+    }
+
+    /// Removes the transactions equal to a previous one
+    ///
+    /// Two transactions are duplicates when they have the same date, amount, category,
+    /// description and account, e.g. when the same worksheet is extracted twice.
+    /// The first occurrence is kept and the accounts are left untouched.
+    /// Returns the number of removed transactions.
+    pub fn dedup(&mut self) -> usize {
+        let count = self.transactions.len();
+        let mut unique: Vec<TransactionEvent> = Vec::with_capacity(count);
+        // transactions are sorted by date, then duplicates are in the same date group
+        let mut group_start = 0;
+        for transaction in self.transactions.drain(..) {
+            if unique
+                .last()
+                .is_some_and(|last| last.date != transaction.date)
+            {
+                group_start = unique.len();
+            }
+            if !unique[group_start..].contains(&transaction) {
+                unique.push(transaction);
+            }
+        }
+        self.transactions = unique;
+        count - self.transactions.len()
+    }
+
+    /// Removes the transfers between the accounts of the registry
+    ///
+    /// A transfer is a pair of transactions on the same date and on different accounts
+    /// with opposite amounts, e.g. money moved from Ale to CartaAle. They would count
+    /// both as income and expense, then they are removed in pairs while the accounts
+    /// keep their values. Returns the number of removed transactions.
+    pub fn drop_internal_transfers(&mut self) -> usize {
+        let mut transfer = vec![false; self.transactions.len()];
+        for i in 0..self.transactions.len() {
+            if transfer[i] || self.transactions[i].amount == 0.0 {
+                continue;
+            }
+            let outgoing = &self.transactions[i];
+            // transactions are sorted by date, then the pair is in the same date group
+            let pair = (i + 1..self.transactions.len())
+                .take_while(|&j| self.transactions[j].date == outgoing.date)
+                .find(|&j| {
+                    !transfer[j]
+                        && self.transactions[j].amount == -outgoing.amount
+                        && self.transactions[j].account != outgoing.account
+                });
+            if let Some(j) = pair {
+                transfer[i] = true;
+                transfer[j] = true;
+            }
+        }
+
+        let count = self.transactions.len();
+        let mut transfer = transfer.into_iter();
+        self.transactions.retain(|_| !transfer.next().unwrap());
+        count - self.transactions.len()
+    }
+
+    /// Recomputes the values of the accounts from their transactions
+    ///
+    /// Each account is reset to its opening value, the one before any transaction,
+    /// and all its transactions are replayed in date order to rebuild its current
+    /// value and history, e.g. after `dedup`. Accounts created by their first
+    /// transaction open with a zero value.
+    pub fn recompute_balances(&mut self) {
+        for account in self.accounts.values_mut() {
+            account.reset();
+        }
+        for transaction in self.transactions.iter() {
+            let name = transaction.account.to_string();
+            match self.accounts.get_mut(&name) {
+                Some(account) => {
+                    account.set_value(account.current_value + transaction.amount, transaction.date)
+                }
+                None => {
+                    self.accounts.insert(
+                        name,
+                        Account::from_transaction(
+                            transaction.account.clone(),
+                            transaction.amount,
+                            transaction.date,
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Moves the transactions of the account `from` to the account `to`
+    ///
+    /// Names are parsed like the ones of the raw files, e.g. "carta ale". When `to`
+    /// already exists the two accounts are merged: the initial value is the sum of
+    /// their initial values and the balances are recomputed from the transactions,
+    /// see `recompute_balances`.
+    ///
+    /// # Errors
+    ///
+    /// One of the names is not a valid account name.
+    pub fn rename_account(&mut self, from: &str, to: &str) -> Result<(), strum::ParseError> {
+        let from = TransactionAccountName::from_str(from)?;
+        let to = TransactionAccountName::from_str(to)?;
+        if from == to {
+            return Ok(());
+        }
+        for transaction in self.transactions.iter_mut().filter(|t| t.account == from) {
+            transaction.account = to.clone();
+        }
+
+        let merged: Vec<Account> = [from.to_string(), to.to_string()]
+            .iter()
+            .filter_map(|name| self.accounts.remove(name))
+            .collect();
+        // Accounts opened by a transaction are created again by their first transaction,
+        // while the initial values of the other ones are kept
+        let given: Vec<&Account> = merged
+            .iter()
+            .filter(|account| !account.is_opened_by_transaction())
+            .collect();
+        if let Some(initial_date) = given.iter().map(|account| account.get_opening().0).min() {
+            let initial_value = given.iter().map(|account| account.get_opening().1).sum();
+            self.accounts.insert(
+                to.to_string(),
+                Account::new(to, initial_value, initial_date),
+            );
+        }
+        self.recompute_balances();
+        Ok(())
+    }
+
+    /// Returns a new registry with the transactions that satisfy `predicate`
+    ///
+    /// The account values are recomputed from the kept transactions: accounts given to
+    /// the registry start again from their opening value, while the ones created by
+    /// their first transaction are created by the first kept one.
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use realearning::model::{
+    ///     account::TransactionAccountName,
+    ///     registry::Registry,
+    ///     transaction::{TransactionCategory, TransactionEvent},
+    /// };
+    ///
+    /// let date = NaiveDate::from_ymd_opt(2023, 5, 9).unwrap();
+    /// let mut registry = Registry::new(None);
+    /// registry.add_batch(vec![
+    ///     TransactionEvent::new(date, 1500.0, TransactionCategory::Stipendio, None, TransactionAccountName::Ale),
+    ///     TransactionEvent::new(date, -600.0, TransactionCategory::Affitto, None, TransactionAccountName::Ale),
+    /// ]);
+    ///
+    /// let expenses = registry.filter(|t| t.amount < 0.0);
+    /// assert_eq!(expenses.iter().count(), 1);
+    /// ```
+    pub fn filter(&self, predicate: impl Fn(&TransactionEvent) -> bool) -> Registry {
+        let accounts: Vec<Account> = self
+            .accounts
+            .values()
+            .filter(|account| !account.is_opened_by_transaction())
+            .cloned()
+            .collect();
+        let mut registry = Registry::new(Some(accounts));
+        for account in registry.accounts.values_mut() {
+            account.reset();
+        }
+        registry.add_batch(
+            self.transactions
+                .iter()
+                .filter(|t| predicate(t))
+                .cloned()
+                .collect(),
+        );
+        registry
+    }
+
+    /// Returns an iterator over the transactions of the registry sorted by date
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use realearning::model::{
+    ///     account::TransactionAccountName,
+    ///     registry::Registry,
+    ///     transaction::{TransactionCategory, TransactionEvent},
+    /// };
+    ///
+    /// let date = NaiveDate::from_ymd_opt(2023, 5, 9).unwrap();
+    /// let mut registry = Registry::new(None);
+    /// registry.add_batch(vec![
+    ///     TransactionEvent::new(date, 1500.0, TransactionCategory::Stipendio, None, TransactionAccountName::Ale),
+    ///     TransactionEvent::new(date, -600.0, TransactionCategory::Affitto, None, TransactionAccountName::Ale),
+    /// ]);
+    ///
+    /// let total: f32 = registry.iter().map(|t| t.amount).sum();
+    /// assert_eq!(total, 900.0);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = &TransactionEvent> {
+        self.transactions.iter()
+    }
+
+    /// Returns the number of transactions in the registry
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Returns true if the registry has no transactions, even if it has accounts
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Returns the dates of the first and last transactions, `None` if the registry is empty
+    pub fn date_range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        // transactions are sorted by date
+        Some((
+            self.transactions.first()?.date,
+            self.transactions.last()?.date,
+        ))
+    }
+
     /// Returns the account names in the regirty as a vector of strings
     pub fn get_accounts(&self) -> Vec<String> {
         self.accounts.keys().map(|x| (*x).clone()).collect()
     }
 
+    /// Splits the registry into one registry per account
+    ///
+    /// Each registry has the transactions of its account, in the same order, and a
+    /// copy of the account with its values. Accounts without transactions get an
+    /// empty registry.
+    pub fn split_by_account(&self) -> HashMap<String, Registry> {
+        let mut registries: HashMap<String, Registry> = self
+            .accounts
+            .iter()
+            .map(|(name, account)| {
+                let registry = Registry {
+                    transactions: Vec::new(),
+                    accounts: HashMap::from([(name.clone(), account.clone())]),
+                };
+                (name.clone(), registry)
+            })
+            .collect();
+        for transaction in self.transactions.iter() {
+            registries
+                .entry(transaction.account.to_string())
+                .or_insert_with(|| Registry::new(None))
+                .transactions
+                .push(transaction.clone());
+        }
+        registries
+    }
+
+    /// Returns the number of transactions of each account in the registry
+    ///
+    /// Accounts without transactions have a count of zero.
+    pub fn transaction_count_by_account(&self) -> HashMap<String, usize> {
+        let mut counts: HashMap<String, usize> =
+            self.accounts.keys().map(|name| (name.clone(), 0)).collect();
+        for transaction in self.transactions.iter() {
+            *counts.entry(transaction.account.to_string()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the summary of each account in the registry sorted by name
+    ///
+    /// The summaries are shown to the user, then they are named with the display
+    /// name of the accounts, e.g. "Carta di Credito Ale".
+    pub fn accounts_summary(&self) -> Vec<AccountSummary> {
+        let counts = self.transaction_count_by_account();
+        let mut summaries: Vec<AccountSummary> = self
+            .accounts
+            .iter()
+            .map(|(name, account)| AccountSummary {
+                name: String::from(account.name.display_name()),
+                current_value: account.current_value,
+                initial_value: account.get_initial_value(),
+                transactions: counts.get(name).copied().unwrap_or(0),
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        summaries
+    }
+
     /// Returns the sum of the initial values of the accounts in the registry
     pub fn get_initial_account_values(&self, accounts: Option<&Vec<String>>) -> f32 {
         let mut value: f32 = 0.;
@@ -92,18 +481,227 @@ impl Registry {
         value
     }
 
+    /// Compares the balance of the accounts with the expected ones
+    ///
+    /// `expected` maps the account names to known balances, e.g. from bank statements.
+    /// Returns the accounts whose computed `current_value` differs from the expected
+    /// one by more than `tolerance`, sorted by account name. Expected accounts missing
+    /// from the registry are reported as well.
+    pub fn validate_balances(
+        &self,
+        expected: &HashMap<String, f32>,
+        tolerance: f32,
+    ) -> Vec<BalanceDiscrepancy> {
+        let mut discrepancies: Vec<BalanceDiscrepancy> = expected
+            .iter()
+            .filter_map(|(name, expected)| {
+                let computed = self.accounts.get(name).map(|a| a.current_value);
+                match computed {
+                    Some(value) if (value - expected).abs() <= tolerance => None,
+                    _ => Some(BalanceDiscrepancy {
+                        account: name.clone(),
+                        expected: *expected,
+                        computed,
+                    }),
+                }
+            })
+            .collect();
+        discrepancies.sort_by(|a, b| a.account.cmp(&b.account));
+        discrepancies
+    }
+
+    /// Returns the transactions whose amount has not the sign expected by their category
+    ///
+    /// Zero amounts and categories without an expected sign are never anomalies.
+    pub fn sign_anomalies(&self) -> Vec<&TransactionEvent> {
+        self.transactions
+            .iter()
+            .filter(|t| t.amount != 0.0 && !t.category.expected_sign().matches(t.amount))
+            .collect()
+    }
+
+    /// Returns the `n` transactions with the largest absolute amount and the given sign
+    ///
+    /// Transactions are sorted by absolute amount in descending order. If the registry
+    /// has less than `n` matching transactions, all of them are returned.
+    pub fn largest_transactions(&self, n: usize, sign: Sign) -> Vec<&TransactionEvent> {
+        self.largest_transactions_filtered(n, sign, None, None)
+    }
+
+    /// Same as `largest_transactions` considering only the transactions of the
+    /// given accounts and in the given date range, both ends included
+    ///
+    /// Accounts are given either by their identifier, as returned by `get_accounts`,
+    /// or by their serialized name, as in the filters of `filtered_dataframe`.
+    pub fn largest_transactions_filtered(
+        &self,
+        n: usize,
+        sign: Sign,
+        accounts: Option<&Vec<String>>,
+        date_range: Option<(&NaiveDate, &NaiveDate)>,
+    ) -> Vec<&TransactionEvent> {
+        let accounts: Option<Vec<TransactionAccountName>> = accounts.map(|names| {
+            names
+                .iter()
+                .filter_map(|name| TransactionAccountName::from_name(name))
+                .collect()
+        });
+        let mut transactions: Vec<&TransactionEvent> = self
+            .transactions
+            .iter()
+            .filter(|t| sign.matches(t.amount))
+            .filter(|t| accounts.as_ref().map_or(true, |a| a.contains(&t.account)))
+            .filter(|t| date_range.map_or(true, |(from, to)| &t.date >= from && &t.date <= to))
+            .collect();
+        transactions.sort_by(|a, b| b.amount.abs().total_cmp(&a.amount.abs()));
+        transactions.truncate(n);
+        transactions
+    }
+
+    /// Net sum of the transaction amounts of each category
+    ///
+    /// `date_range`: Optional parameter with the inclusive range of dates to consider
+    pub fn category_totals(
+        &self,
+        date_range: Option<(&NaiveDate, &NaiveDate)>,
+    ) -> HashMap<TransactionCategory, f32> {
+        let mut totals: HashMap<TransactionCategory, f32> = HashMap::new();
+        for transaction in self
+            .transactions
+            .iter()
+            .filter(|t| date_range.map_or(true, |(from, to)| &t.date >= from && &t.date <= to))
+        {
+            *totals.entry(transaction.category.clone()).or_insert(0.0) += transaction.amount;
+        }
+        totals
+    }
+
+    /// Assign a category to the uncategorized transactions by keywords in their description
+    ///
+    /// Rules are pairs of keyword and category evaluated in order: the first keyword
+    /// contained in the description, ignoring the case, sets the category.
+    /// Categorized transactions and the ones without a match are left untouched.
+    pub fn recategorize_by_keywords(&mut self, rules: &[(String, TransactionCategory)]) {
+        let rules: Vec<(String, &TransactionCategory)> = rules
+            .iter()
+            .map(|(keyword, category)| (keyword.to_lowercase(), category))
+            .collect();
+
+        for transaction in self.transactions.iter_mut() {
+            if !matches!(transaction.category, TransactionCategory::Uncategorized) {
+                continue;
+            }
+            if let Some(description) = &transaction.description {
+                let description = description.to_lowercase();
+                if let Some((_, category)) = rules
+                    .iter()
+                    .find(|(keyword, _)| description.contains(keyword))
+                {
+                    transaction.category = (*category).clone();
+                }
+            }
+        }
+    }
+
     /// Export TranactionEvent to Polars DataFrame
     ///
-    /// First, it serializes it as a JSON string, then
-    /// it uses the Polars JsonReader to create the DataFrame
+    /// The columns are built directly from the transactions. Categories and accounts
+    /// have the names of their serialization and amounts keep the decimal digits
+    /// of their `f32` value.
     pub fn to_dataframe(&self) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let dates: Vec<NaiveDate> = self.transactions.iter().map(|t| t.date).collect();
+        let amounts: Vec<f64> = self
+            .transactions
+            .iter()
+            .map(|t| decimal_f64(t.amount))
+            .collect();
+        let categories: Vec<String> = self
+            .transactions
+            .iter()
+            .map(|t| serialized_name(&t.category))
+            .collect::<Result<_, _>>()?;
+        let descriptions: Vec<Option<&str>> = self
+            .transactions
+            .iter()
+            .map(|t| t.description.as_deref())
+            .collect();
+        let accounts: Vec<String> = self
+            .transactions
+            .iter()
+            .map(|t| serialized_name(&t.account))
+            .collect::<Result<_, _>>()?;
+
+        Ok(DataFrame::new(vec![
+            Series::new(DATE_COLUMN, dates),
+            Series::new(AMOUNT_COLUMN, amounts),
+            Series::new(CATEGORY_COLUMN, categories),
+            Series::new(DESCRIPTION_COLUMN, descriptions),
+            Series::new(ACCOUNT_COLUMN, accounts),
+        ])?)
+    }
+
+    /// Returns the dataframe of the registry with the transactions of the given accounts
+    /// and in the given date range, both ends included
+    ///
+    /// It has the columns of `to_dataframe`, hence it can be used for custom
+    /// aggregations with the same filters of the plots.
+    ///
+    /// Accounts are given either by their identifier, as returned by `get_accounts`,
+    /// or by their serialized name, e.g. "carta ale" or "CartaAle".
+    pub fn filtered_dataframe(
+        &self,
+        accounts: Option<&Vec<String>>,
+        date_range: Option<(&NaiveDate, &NaiveDate)>,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let mut df = self.to_dataframe()?.lazy();
+
+        if let Some(vector) = accounts {
+            let accounts = Series::new("account_list", serialized_account_names(vector)?);
+            df = df.filter(col(ACCOUNT_COLUMN).is_in(lit(accounts)));
+        }
+
+        if let Some((from, to)) = date_range {
+            df = df.filter(
+                col(DATE_COLUMN)
+                    .dt()
+                    .strftime("%Y-%m-%d")
+                    .gt_eq(lit(&from.to_string()[..])),
+            );
+            df = df.filter(
+                col(DATE_COLUMN)
+                    .dt()
+                    .strftime("%Y-%m-%d")
+                    .lt_eq(lit(&to.to_string()[..])),
+            );
+        }
+
+        Ok(df.collect()?)
+    }
+
+    /// Export TranactionEvent to Polars DataFrame through JSON
+    ///
+    /// First, it serializes it as a JSON string, then
+    /// it uses the Polars JsonReader to create the DataFrame.
+    /// An empty registry gives an empty DataFrame with the same columns.
+    /// It gives the same DataFrame of `to_dataframe` but it is slower.
+    pub fn to_dataframe_json(&self) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        if self.transactions.is_empty() {
+            return Ok(DataFrame::new(vec![
+                Series::new_empty(DATE_COLUMN, &DataType::Date),
+                Series::new_empty(AMOUNT_COLUMN, &DataType::Float64),
+                Series::new_empty(CATEGORY_COLUMN, &DataType::Utf8),
+                Series::new_empty(DESCRIPTION_COLUMN, &DataType::Utf8),
+                Series::new_empty(ACCOUNT_COLUMN, &DataType::Utf8),
+            ])?);
+        }
         let myschema = Schema::from(
             vec![
-                Field::new("date", DataType::Float32),
-                Field::new("amount", DataType::Float32),
-                Field::new("category", DataType::Categorical(None)),
-                Field::new("description", DataType::Utf8),
-                Field::new("account", DataType::Categorical(None)),
+                // chrono serializes the dates as %Y-%m-%d strings
+                Field::new(DATE_COLUMN, DataType::Utf8),
+                Field::new(AMOUNT_COLUMN, DataType::Float64),
+                Field::new(CATEGORY_COLUMN, DataType::Categorical(None)),
+                Field::new(DESCRIPTION_COLUMN, DataType::Utf8),
+                Field::new(ACCOUNT_COLUMN, DataType::Categorical(None)),
             ]
             .into_iter(),
         );
@@ -113,7 +711,7 @@ impl Registry {
 
         Ok(df
             .lazy()
-            .with_column(col("date").str().strptime(StrpTimeOptions {
+            .with_column(col(DATE_COLUMN).str().strptime(StrpTimeOptions {
                 date_dtype: DataType::Date,
                 fmt: Some("%Y-%m-%d".into()),
                 strict: false,
@@ -125,6 +723,13 @@ impl Registry {
             .collect()?)
     }
 
+    /// Dumps the registry dataframe as parquet
+    pub fn to_parquet(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut df = self.to_dataframe()?;
+        ParquetWriter::new(File::create(path)?).finish(&mut df)?;
+        Ok(())
+    }
+
     /// Build a regstry from a dumped csv
     pub fn from_csv(path: &str) -> Result<Registry, io::Error> {
         let file = File::open(path)?;
@@ -138,8 +743,14 @@ impl Registry {
     }
 
     /// Dumps the registry as csv
+    ///
+    /// An existing file is overwritten, use `append_to_csv` to add the transactions to it.
     pub fn to_csv(&self, path: &str) -> Result<(), io::Error> {
-        let file = OpenOptions::new().write(true).create(true).open(path)?;
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
 
         let mut wtr = csv::Writer::from_writer(file);
         for transaction in &self.transactions {
@@ -148,27 +759,101 @@ impl Registry {
         wtr.flush()?;
         Ok(())
     }
+
+    /// Appends the transactions of the registry to a csv dump
+    ///
+    /// Differently from `to_csv`, the rows are added at the end of an existing file
+    /// and the header is written only when the file is missing or empty, then the
+    /// file can be read back with `from_csv`.
+    pub fn append_to_csv(&self, path: &str) -> Result<(), io::Error> {
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        let has_headers = file.metadata()?.len() == 0;
+
+        let mut wtr = csv::WriterBuilder::new()
+            .has_headers(has_headers)
+            .from_writer(file);
+        for transaction in &self.transactions {
+            wtr.serialize(transaction)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Returns the transactions as a text table with aligned columns
+    ///
+    /// The table has a header and a row for each transaction with date, account,
+    /// category, amount and description. When `limit` is set only the last `limit`
+    /// transactions are shown. Amounts are right aligned with two decimals.
+    pub fn to_table_string(&self, limit: Option<usize>) -> String {
+        let shown = limit.map_or(self.transactions.len(), |n| n.min(self.transactions.len()));
+        let header = ["date", "account", "category", "amount", "description"].map(String::from);
+        let rows: Vec<[String; 5]> = self.transactions[self.transactions.len() - shown..]
+            .iter()
+            .map(|t| {
+                [
+                    t.date.to_string(),
+                    String::from(t.account.display_name()),
+                    t.category.to_string(),
+                    format!("{:.2}", t.amount),
+                    t.description.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+
+        let mut widths = header.clone().map(|x| x.chars().count());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let format_row = |row: &[String; 5]| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .enumerate()
+                .map(|(i, (cell, width))| match i {
+                    3 => format!("{cell:>width$}"),
+                    _ => format!("{cell:<width$}"),
+                })
+                .collect();
+            cells.join(" | ").trim_end().to_string()
+        };
+
+        let mut lines = vec![format_row(&header)];
+        lines.push(
+            widths
+                .iter()
+                .map(|width| "-".repeat(*width))
+                .collect::<Vec<String>>()
+                .join("-+-"),
+        );
+        lines.extend(rows.iter().map(format_row));
+        lines.join("\n")
+    }
 }
 
 impl fmt::Display for Registry {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "The registry has {} accounts:\n\n", self.accounts.len())?;
-        for (name, account) in &self.accounts {
-            writeln!(f, "\t> {}:\t{}€", name, account.current_value)?;
+        for account in self.accounts.values() {
+            writeln!(
+                f,
+                "\t> {}:\t{}€",
+                account.name.display_name(),
+                account.current_value
+            )?;
         }
         let transaction_len = self.transactions.len();
 
         if transaction_len > 0 {
-            let num_last_transactions = match transaction_len {
-                1..=5 => transaction_len,
-                _ => transaction_len - 5,
-            };
+            let shown = transaction_len.min(DISPLAYED_TRANSACTIONS);
             write!(
                 f,
-                "\n\nThere are {} transactions in the registry:\n\n",
-                transaction_len
+                "\n\nShowing the last {} of {} transactions:\n\n",
+                shown, transaction_len
             )?;
-            for transaction in &self.transactions[num_last_transactions..] {
+            for transaction in &self.transactions[transaction_len - shown..] {
                 writeln!(f, "\t- {}", transaction)?
             }
         }
@@ -176,32 +861,98 @@ impl fmt::Display for Registry {
     }
 }
 
+impl<'a> IntoIterator for &'a Registry {
+    type Item = &'a TransactionEvent;
+    type IntoIter = std::slice::Iter<'a, TransactionEvent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.transactions.iter()
+    }
+}
+
 impl Add for Registry {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
-        let mut transactions: Vec<TransactionEvent> = Vec::new();
-        transactions.extend(self.transactions);
-        transactions.extend(other.transactions);
+        let mut registry = self;
+        registry.extend(other);
+        registry
+    }
+}
 
-        // We use a hashmap to store the references of the accounts inside the
-        // two registries. Then we use it to merge the accouts to get the final
-        // version of the registry to return
-        let mut accounts_to_insert: HashMap<String, Account> = HashMap::new();
-        for (name, self_account) in self.accounts {
-            accounts_to_insert.insert(name, self_account);
-        }
-        for (name, other_account) in other.accounts {
-            let self_entry_ref: Option<Account> = accounts_to_insert.remove(&name);
-            match self_entry_ref {
-                Some(r) => accounts_to_insert.insert(name, r + other_account),
-                None => accounts_to_insert.insert(name, other_account),
-            };
-        }
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use polars::prelude::DataType;
 
-        Registry {
-            accounts: accounts_to_insert,
-            transactions,
-        }
+    use crate::model::{
+        account::TransactionAccountName,
+        transaction::{TransactionCategory, TransactionEvent},
+    };
+
+    use super::{Registry, AMOUNT_COLUMN, DATE_COLUMN};
+
+    fn transaction(
+        amount: f32,
+        category: TransactionCategory,
+        description: Option<&str>,
+    ) -> TransactionEvent {
+        TransactionEvent::new(
+            NaiveDate::parse_from_str("2023-05-09", "%Y-%m-%d").unwrap(),
+            amount,
+            category,
+            description.map(String::from),
+            TransactionAccountName::Ale,
+        )
+    }
+
+    #[test]
+    fn dataframe_without_json() {
+        let mut registry = Registry::new(None);
+        assert!(registry
+            .to_dataframe()
+            .unwrap()
+            .frame_equal_missing(&registry.to_dataframe_json().unwrap()));
+
+        let mut card_payment = transaction(-0.01, TransactionCategory::CartaDiCredito, None);
+        card_payment.account = TransactionAccountName::BuonoPasto;
+        registry.add_batch(vec![
+            transaction(1500.0, TransactionCategory::Stipendio, Some("maggio")),
+            transaction(-80.3, TransactionCategory::Spesa, None),
+            card_payment,
+        ]);
+        let df = registry.to_dataframe().unwrap();
+        assert!(df.frame_equal_missing(&registry.to_dataframe_json().unwrap()));
+        let amounts: Vec<f64> = df
+            .column(AMOUNT_COLUMN)
+            .unwrap()
+            .f64()
+            .unwrap()
+            .into_no_null_iter()
+            .collect();
+        assert_eq!(amounts[2], -0.01);
+    }
+
+    #[test]
+    fn dataframe_json_dates() {
+        let event = transaction(-80.3, TransactionCategory::Spesa, None);
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["date"], serde_json::json!("2023-05-09"));
+        let roundtrip: TransactionEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(roundtrip.date, event.date);
+
+        let mut registry = Registry::new(None);
+        registry.add_batch(vec![event]);
+        let df = registry.to_dataframe_json().unwrap();
+        let dates = df.column(DATE_COLUMN).unwrap();
+        assert_eq!(dates.dtype(), &DataType::Date);
+        assert_eq!(dates.null_count(), 0);
+        assert!(dates.series_equal(
+            registry
+                .to_dataframe()
+                .unwrap()
+                .column(DATE_COLUMN)
+                .unwrap()
+        ));
     }
 }
@@ -5,15 +5,19 @@ use polars::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
     fmt::{self},
+    hash::{Hash, Hasher},
     io::Cursor,
 };
 use strum_macros::{Display, EnumString};
 
 use super::account::TransactionAccountName;
+use crate::io::number_parser::deserialize_amount;
 
 /// TransactionCategory enumeration contains
 /// the categories a transaction event can belong to.
-#[derive(EnumString, Display, Serialize, Deserialize)]
+#[derive(
+    EnumString, Display, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash,
+)]
 pub enum TransactionCategory {
     #[strum(ascii_case_insensitive)]
     Affitto,
@@ -57,6 +61,115 @@ pub enum TransactionCategory {
     Vista,
     #[strum(ascii_case_insensitive)]
     Vacanza,
+    /// Default category of transactions that still need to be categorized
+    #[default]
+    #[strum(ascii_case_insensitive)]
+    Uncategorized,
+}
+
+impl TransactionCategory {
+    /// Returns the sign that the amounts of the category are expected to have
+    ///
+    /// Categories that can be both incomes and expenses, like transfers
+    /// between accounts or gifts, return `Sign::Any`.
+    pub fn expected_sign(&self) -> Sign {
+        match self {
+            TransactionCategory::Stipendio => Sign::Income,
+            TransactionCategory::Banca
+            | TransactionCategory::CartaDiCredito
+            | TransactionCategory::Regalo
+            | TransactionCategory::RitiroBancomat
+            | TransactionCategory::Varie
+            | TransactionCategory::Uncategorized => Sign::Any,
+            TransactionCategory::Affitto
+            | TransactionCategory::Auto
+            | TransactionCategory::Bolletta
+            | TransactionCategory::Pasto
+            | TransactionCategory::PranzoLavoro
+            | TransactionCategory::RataAuto
+            | TransactionCategory::Sanita
+            | TransactionCategory::Scarpe
+            | TransactionCategory::Spesa
+            | TransactionCategory::Telefono
+            | TransactionCategory::Treno
+            | TransactionCategory::Uscite
+            | TransactionCategory::Vestiti
+            | TransactionCategory::Vista
+            | TransactionCategory::Vacanza => Sign::Expense,
+        }
+    }
+
+    /// Returns true if the category is an income, like `Stipendio`
+    pub fn is_income_by_default(&self) -> bool {
+        self.expected_sign() == Sign::Income
+    }
+
+    /// Returns the category of a serialized name, e.g. "PranzoLavoro"
+    ///
+    /// The dataframes of the registry name the categories this way, the
+    /// `FromStr` names are the ones of the spreadsheets, e.g. "pranzo lavoro".
+    pub fn from_serialized_name(name: &str) -> Option<TransactionCategory> {
+        serde_json::from_value(serde_json::Value::from(name)).ok()
+    }
+}
+
+/// Sign of the amount of a transaction used to select transactions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sign {
+    /// Transactions with a negative amount
+    Expense,
+    /// Transactions with a positive amount
+    Income,
+    /// Any transaction
+    Any,
+}
+
+impl Sign {
+    /// Returns true if the amount has this sign
+    pub fn matches(&self, amount: f32) -> bool {
+        match self {
+            Sign::Expense => amount < 0.0,
+            Sign::Income => amount > 0.0,
+            Sign::Any => true,
+        }
+    }
+}
+
+/// Side of a transaction given by the sign of its amount
+///
+/// Unlike `Sign`, that selects transactions, a flow is the side of a single
+/// transaction, then zero amounts have their own flow instead of being neither
+/// an income nor an expense.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    /// Positive amount
+    Income,
+    /// Negative amount
+    Expense,
+    /// Zero amount, like `NaN` that has no sign
+    Zero,
+}
+
+impl Flow {
+    /// Returns the flow of an amount
+    pub fn of(amount: f64) -> Flow {
+        if amount > 0.0 {
+            Flow::Income
+        } else if amount < 0.0 {
+            Flow::Expense
+        } else {
+            Flow::Zero
+        }
+    }
+
+    /// Returns the expression selecting the amounts of this flow, to filter dataframes
+    pub fn filter(&self, amount: Expr) -> Expr {
+        match self {
+            Flow::Income => amount.gt(lit(0.0)),
+            Flow::Expense => amount.lt(lit(0.0)),
+            Flow::Zero => amount.clone().gt(lit(0.0)).or(amount.lt(lit(0.0))).not(),
+        }
+    }
 }
 
 /// TransactionEvent struct that define a transaction.
@@ -67,9 +180,15 @@ pub enum TransactionCategory {
 /// - **category**: type of transaction
 /// - **description**: optional description of the transaction
 /// - **source**: source of the transaction
-#[derive(Serialize, Deserialize)]
+///
+/// Transactions are equal when all their fields are equal, the amounts are
+/// compared by their bits so that equality is reflexive and consistent with `Hash`:
+/// `0.0` and `-0.0` are different amounts while `NaN` is equal to itself.
+#[derive(Serialize, Deserialize, Clone)]
 pub struct TransactionEvent {
     pub date: NaiveDate,
+    /// Amounts written as text are parsed with `NumberParser`, e.g. "1.234,56€"
+    #[serde(deserialize_with = "deserialize_amount")]
     pub amount: f32,
     pub category: TransactionCategory,
     pub description: Option<String>,
@@ -93,6 +212,11 @@ impl TransactionEvent {
         }
     }
 
+    /// Returns whether the transaction is an income, an expense or has a zero amount
+    pub fn flow(&self) -> Flow {
+        Flow::of(f64::from(self.amount))
+    }
+
     /// Export TranactionEvent to Polars DataFrame
     ///
     /// First, it serializes it as a JSON string, then
@@ -102,6 +226,28 @@ impl TransactionEvent {
     }
 }
 
+impl PartialEq for TransactionEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.date == other.date
+            && self.amount.to_bits() == other.amount.to_bits()
+            && self.category == other.category
+            && self.description == other.description
+            && self.account == other.account
+    }
+}
+
+impl Eq for TransactionEvent {}
+
+impl Hash for TransactionEvent {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.date.hash(state);
+        self.amount.to_bits().hash(state);
+        self.category.hash(state);
+        self.description.hash(state);
+        self.account.hash(state);
+    }
+}
+
 impl fmt::Display for TransactionEvent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -110,7 +256,7 @@ impl fmt::Display for TransactionEvent {
             self.date,
             self.category,
             self.amount,
-            self.account,
+            self.account.display_name(),
             match &self.description {
                 Some(s) => s,
                 None => "missing",
@@ -121,11 +267,23 @@ impl fmt::Display for TransactionEvent {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
     use chrono::NaiveDate;
 
     use crate::model::account::TransactionAccountName;
 
-    use super::{TransactionCategory, TransactionEvent};
+    use super::{Flow, Sign, TransactionCategory, TransactionEvent};
+
+    fn transaction(amount: f32, description: Option<&str>) -> TransactionEvent {
+        TransactionEvent::new(
+            NaiveDate::from_ymd_opt(2023, 5, 9).unwrap(),
+            amount,
+            TransactionCategory::Spesa,
+            description.map(String::from),
+            TransactionAccountName::Ale,
+        )
+    }
 
     #[test]
     fn create_transaction_event() {
@@ -155,4 +313,58 @@ mod tests {
             other_transaction.account.to_string()
         );
     }
+
+    #[test]
+    fn category_expected_sign() {
+        assert!(TransactionCategory::Stipendio.is_income_by_default());
+        assert!(!TransactionCategory::Affitto.is_income_by_default());
+        assert_eq!(TransactionCategory::Affitto.expected_sign(), Sign::Expense);
+        assert_eq!(TransactionCategory::Regalo.expected_sign(), Sign::Any);
+        assert_eq!(TransactionCategory::default().expected_sign(), Sign::Any);
+    }
+
+    #[test]
+    fn transaction_flow() {
+        assert_eq!(transaction(1500.0, None).flow(), Flow::Income);
+        assert_eq!(transaction(-12.5, None).flow(), Flow::Expense);
+        assert_eq!(transaction(0.0, None).flow(), Flow::Zero);
+        assert_eq!(transaction(-0.0, None).flow(), Flow::Zero);
+        assert_eq!(transaction(f32::NAN, None).flow(), Flow::Zero);
+    }
+
+    #[test]
+    fn transaction_equality() {
+        assert!(transaction(-12.5, Some("esselunga")) == transaction(-12.5, Some("esselunga")));
+        assert!(transaction(-12.5, Some("esselunga")) != transaction(-12.5, None));
+        assert!(transaction(-12.5, None) != transaction(-12.0, None));
+        assert!(transaction(0.0, None) != transaction(-0.0, None));
+        assert!(transaction(f32::NAN, None) == transaction(f32::NAN, None));
+
+        let mut other_account = transaction(-12.5, None);
+        other_account.account = TransactionAccountName::Giulia;
+        assert!(transaction(-12.5, None) != other_account);
+    }
+
+    #[test]
+    fn transaction_hash() {
+        let transactions: HashSet<TransactionEvent> = [
+            transaction(-12.5, None),
+            transaction(-12.5, None),
+            transaction(-12.5, Some("esselunga")),
+            transaction(30.0, None),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(transactions.len(), 3);
+        assert!(transactions.contains(&transaction(30.0, None)));
+
+        let accounts: HashSet<TransactionAccountName> = [
+            TransactionAccountName::Ale,
+            TransactionAccountName::Giulia,
+            TransactionAccountName::Ale,
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(accounts.len(), 2);
+    }
 }
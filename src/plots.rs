@@ -1,15 +1,25 @@
 pub mod extraction;
+mod figure_backend;
+pub mod plot_config;
 pub mod plot_registry;
 
-mod plot_errors {
+pub mod plot_errors {
     use std::{error, fmt};
 
-    #[derive(Debug, Clone)]
-    pub struct PlotError;
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum PlotError {
+        /// There are no transactions left to plot after the filters
+        NoData,
+        /// A parameter of the plot has a value that cannot be drawn
+        InvalidParameter(String),
+    }
 
     impl fmt::Display for PlotError {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "invalid first item to double")
+            match self {
+                PlotError::NoData => write!(f, "there are no transactions to plot"),
+                PlotError::InvalidParameter(reason) => write!(f, "invalid parameter: {}", reason),
+            }
         }
     }
 
@@ -17,10 +27,282 @@ mod plot_errors {
 }
 
 pub mod plot_utils {
+    use chrono::format::{Item, StrftimeItems};
+    use plotters::style::RGBAColor;
+
+    /// Default format of the dates in the plot labels, e.g. 2023-05-09
+    pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+    /// Returns an error if `date_format` has specifiers unknown to chrono,
+    /// formatting the dates of the labels with it would panic
+    pub fn validate_date_format(date_format: &str) -> Result<(), String> {
+        if StrftimeItems::new(date_format).any(|item| matches!(item, Item::Error)) {
+            Err(format!("\"{date_format}\" is not a valid date format"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Number of gridlines `nice_step` aims for
+    const TARGET_GRIDLINES: f64 = 20.0;
+
+    /// Returns a step for the axis over `range` that is 1, 2 or 5 times a power of ten
+    ///
+    /// The step is the smallest of such values that gives at most 20 gridlines,
+    /// hence between 8 and 20 of them. Empty or invalid ranges get a step of 1.
+    pub fn nice_step(range: (f64, f64)) -> f64 {
+        let span = range.1 - range.0;
+        if !span.is_finite() || span <= 0.0 {
+            return 1.0;
+        }
+        let raw_step = span / TARGET_GRIDLINES;
+        let magnitude = 10f64.powf(raw_step.log10().floor());
+        let normalized = raw_step / magnitude;
+        let nice = if normalized <= 1.0 {
+            1.0
+        } else if normalized <= 2.0 {
+            2.0
+        } else if normalized <= 5.0 {
+            5.0
+        } else {
+            10.0
+        };
+        nice * magnitude
+    }
+
+    /// Separators used to format the numbers of the plot labels
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct NumberFormat {
+        /// Separator between groups of thousands, none by default
+        pub thousands_separator: Option<char>,
+        /// Separator of the decimal digits, `.` by default
+        pub decimal_separator: char,
+    }
+
+    impl Default for NumberFormat {
+        fn default() -> Self {
+            NumberFormat {
+                thousands_separator: None,
+                decimal_separator: '.',
+            }
+        }
+    }
+
+    impl NumberFormat {
+        /// Formats `x` rounded to `decimals` decimal digits
+        pub fn format(&self, x: f64, decimals: usize) -> String {
+            let formatted = format!("{:.*}", decimals, x);
+            let (sign, digits) = match formatted.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", formatted.as_str()),
+            };
+            let (integer, fraction) = match digits.split_once('.') {
+                Some((integer, fraction)) => (integer, Some(fraction)),
+                None => (digits, None),
+            };
+
+            let mut result = String::from(sign);
+            for (i, digit) in integer.chars().enumerate() {
+                if let Some(separator) = self.thousands_separator {
+                    if i > 0 && (integer.len() - i) % 3 == 0 {
+                        result.push(separator);
+                    }
+                }
+                result.push(digit);
+            }
+            if let Some(fraction) = fraction {
+                result.push(self.decimal_separator);
+                result.push_str(fraction);
+            }
+            result
+        }
+    }
+
+    /// Returns the color at `t` of the linear gradient from `from` to `to`
+    ///
+    /// `t` is clamped between 0, giving `from`, and 1, giving `to`.
+    pub fn interpolate_color(from: RGBAColor, to: RGBAColor, t: f64) -> RGBAColor {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let channel =
+            |x: u8, y: u8| (f64::from(x) + (f64::from(y) - f64::from(x)) * t).round() as u8;
+        RGBAColor(
+            channel(from.0, to.0),
+            channel(from.1, to.1),
+            channel(from.2, to.2),
+            from.3 + (to.3 - from.3) * t,
+        )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use plotters::style::RGBAColor;
+
+        use super::{interpolate_color, nice_step, NumberFormat};
+
+        fn assert_close(x: f64, y: f64) {
+            assert!((x - y).abs() < 1e-9 * y.abs(), "{x} != {y}");
+        }
+
+        #[test]
+        fn nice_steps() {
+            assert_close(nice_step((0.0, 100.0)), 5.0);
+            assert_close(nice_step((0.0, 1000.0)), 50.0);
+            assert_close(nice_step((-1234.0, 5678.0)), 500.0);
+            assert_close(nice_step((0.0, 0.3)), 0.02);
+            assert_close(nice_step((10.0, 13.0)), 0.2);
+            assert_close(nice_step((0.0, 150000.0)), 10000.0);
+        }
+
+        #[test]
+        fn nice_step_gridlines() {
+            for range in [(0.0, 7.0), (-50.0, 3.0), (1.0, 123456.0), (-0.01, 0.0)] {
+                let gridlines = (range.1 - range.0) / nice_step(range);
+                assert!((8.0..=20.0).contains(&gridlines), "{range:?}: {gridlines}");
+            }
+        }
+
+        #[test]
+        fn color_interpolation() {
+            let from = RGBAColor(0, 100, 200, 1.0);
+            let to = RGBAColor(200, 100, 0, 1.0);
+            assert_eq!(interpolate_color(from, to, 0.0), from);
+            assert_eq!(interpolate_color(from, to, 1.0), to);
+            assert_eq!(
+                interpolate_color(from, to, 0.25),
+                RGBAColor(50, 100, 150, 1.0)
+            );
+            assert_eq!(interpolate_color(from, to, 2.0), to);
+        }
+
+        #[test]
+        fn number_formatting() {
+            let default = NumberFormat::default();
+            assert_eq!(default.format(12345.0, 0), "12345");
+            assert_eq!(default.format(-1234.567, 2), "-1234.57");
+
+            let european = NumberFormat {
+                thousands_separator: Some('.'),
+                decimal_separator: ',',
+            };
+            assert_eq!(european.format(12345.0, 0), "12.345");
+            assert_eq!(european.format(-1234567.891, 2), "-1.234.567,89");
+            assert_eq!(european.format(999.0, 0), "999");
+            assert_eq!(european.format(-100.0, 1), "-100,0");
+
+            let english = NumberFormat {
+                thousands_separator: Some(','),
+                decimal_separator: '.',
+            };
+            assert_eq!(english.format(1000.0, 0), "1,000");
+        }
+
+        #[test]
+        fn nice_step_empty_range() {
+            assert_eq!(nice_step((5.0, 5.0)), 1.0);
+            assert_eq!(nice_step((5.0, 1.0)), 1.0);
+            assert_eq!(nice_step((f64::NAN, 1.0)), 1.0);
+        }
+    }
+
     pub mod resolution {
+        use std::{fmt, str::FromStr};
+
         pub const R720: (u32, u32) = (1280, 720);
         pub const R1080: (u32, u32) = (1920, 1080);
         pub const R4K: (u32, u32) = (3840, 2160);
+
+        /// Resolution of the plots, either a preset or a custom size
+        ///
+        /// It is parsed from `720`, `1080`, `4k` or from a custom `WIDTHxHEIGHT`
+        /// string like `1600x900`.
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum ResolutionEnum {
+            R720,
+            R1080,
+            R4K,
+            Custom(u32, u32),
+        }
+
+        impl ResolutionEnum {
+            /// Returns width and height in pixels of the resolution
+            pub fn size(&self) -> (u32, u32) {
+                match self {
+                    ResolutionEnum::R720 => R720,
+                    ResolutionEnum::R1080 => R1080,
+                    ResolutionEnum::R4K => R4K,
+                    ResolutionEnum::Custom(width, height) => (*width, *height),
+                }
+            }
+        }
+
+        impl FromStr for ResolutionEnum {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s.to_lowercase().as_str() {
+                    "720" => Ok(ResolutionEnum::R720),
+                    "1080" => Ok(ResolutionEnum::R1080),
+                    "4k" => Ok(ResolutionEnum::R4K),
+                    custom => {
+                        let invalid = || {
+                            format!(
+                                "invalid resolution \"{s}\", expected 720, 1080, 4k or WIDTHxHEIGHT"
+                            )
+                        };
+                        let (width, height) = custom.split_once('x').ok_or_else(invalid)?;
+                        let width: u32 = width.trim().parse().map_err(|_| invalid())?;
+                        let height: u32 = height.trim().parse().map_err(|_| invalid())?;
+                        if width == 0 || height == 0 {
+                            return Err(format!(
+                                "invalid resolution \"{s}\", width and height must be greater than 0"
+                            ));
+                        }
+                        Ok(ResolutionEnum::Custom(width, height))
+                    }
+                }
+            }
+        }
+
+        impl fmt::Display for ResolutionEnum {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match self {
+                    ResolutionEnum::R720 => write!(f, "720"),
+                    ResolutionEnum::R1080 => write!(f, "1080"),
+                    ResolutionEnum::R4K => write!(f, "4k"),
+                    ResolutionEnum::Custom(width, height) => write!(f, "{width}x{height}"),
+                }
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use std::str::FromStr;
+
+            use super::*;
+
+            #[test]
+            fn resolution_sizes() {
+                assert_eq!(ResolutionEnum::from_str("720").unwrap().size(), R720);
+                assert_eq!(ResolutionEnum::from_str("1080").unwrap().size(), R1080);
+                assert_eq!(ResolutionEnum::from_str("4k").unwrap().size(), R4K);
+                assert_eq!(ResolutionEnum::from_str("4K").unwrap().size(), R4K);
+                assert!(ResolutionEnum::from_str("480").is_err());
+            }
+
+            #[test]
+            fn custom_resolution() {
+                let resolution = ResolutionEnum::from_str("1600x900").unwrap();
+                assert_eq!(resolution, ResolutionEnum::Custom(1600, 900));
+                assert_eq!(resolution.size(), (1600, 900));
+                assert_eq!(resolution.to_string(), "1600x900");
+
+                assert!(ResolutionEnum::from_str("0x900").is_err());
+                assert!(ResolutionEnum::from_str("1600x0").is_err());
+                assert!(ResolutionEnum::from_str("1600x").is_err());
+                assert!(ResolutionEnum::from_str("1600*900").is_err());
+                assert!(ResolutionEnum::from_str("-1600x900").is_err());
+            }
+        }
     }
 
     pub mod palettes {
@@ -30,39 +312,124 @@ pub mod plot_utils {
         expand palettes: https://mycolor.space/?hex=%23D6D1B1&sub=1
         from hex to rgb: https://www.rapidtables.com/convert/color/hex-to-rgb.html
         */
+        use std::borrow::Cow;
+
         use plotters::style::RGBAColor;
+        use strum_macros::{Display, EnumString};
 
+        /// Colors of the plots
+        ///
+        /// The plots pick the colors of the series by index, indexes beyond the number
+        /// of colors wrap around to the first color.
+        #[derive(Clone, Debug, PartialEq)]
         pub struct Palette {
             pub background: RGBAColor,
             pub mesh: RGBAColor,
-            pub colors: [RGBAColor; 20],
+            pub colors: Cow<'static, [RGBAColor]>,
         }
+
+        impl Palette {
+            /// Create a new palette with any number of colors
+            ///
+            /// # Panics
+            ///
+            /// Panics if `colors` is empty.
+            pub fn new(
+                background: RGBAColor,
+                mesh: RGBAColor,
+                colors: impl Into<Cow<'static, [RGBAColor]>>,
+            ) -> Palette {
+                let colors = colors.into();
+                assert!(!colors.is_empty(), "a palette needs at least one color");
+                Palette {
+                    background,
+                    mesh,
+                    colors,
+                }
+            }
+
+            /// Returns the color at `index`, wrapping around the colors of the palette
+            pub fn color(&self, index: usize) -> RGBAColor {
+                self.colors[index % self.colors.len()]
+            }
+        }
+
+        impl Default for Palette {
+            /// The red palette
+            fn default() -> Self {
+                RED_PALETTE.clone()
+            }
+        }
+
+        const RED_COLORS: [RGBAColor; 20] = [
+            RGBAColor(109, 118, 152, 1.0),
+            RGBAColor(185, 186, 163, 1.0),
+            RGBAColor(214, 213, 201, 1.0),
+            RGBAColor(162, 44, 41, 1.0),
+            RGBAColor(148, 83, 35, 1.0),
+            RGBAColor(85, 68, 115, 1.0),
+            RGBAColor(123, 150, 224, 1.0),
+            RGBAColor(151, 42, 80, 1.0),
+            RGBAColor(187, 120, 110, 1.0),
+            RGBAColor(109, 118, 152, 1.0),
+            RGBAColor(172, 99, 170, 1.0),
+            RGBAColor(56, 99, 0, 1.0),
+            RGBAColor(209, 231, 224, 1.0),
+            RGBAColor(97, 168, 255, 1.0),
+            RGBAColor(170, 107, 112, 1.0),
+            RGBAColor(252, 133, 178, 1.0),
+            RGBAColor(0, 86, 178, 1.0),
+            RGBAColor(168, 174, 156, 1.0),
+            RGBAColor(255, 120, 106, 1.0),
+            RGBAColor(137, 114, 110, 1.0),
+        ];
+
         pub const RED_PALETTE: Palette = Palette {
             background: RGBAColor(248, 247, 241, 1.0),
             mesh: RGBAColor(200, 200, 200, 1.0),
-            colors: [
-                RGBAColor(109, 118, 152, 1.0),
-                RGBAColor(185, 186, 163, 1.0),
-                RGBAColor(214, 213, 201, 1.0),
-                RGBAColor(162, 44, 41, 1.0),
-                RGBAColor(148, 83, 35, 1.0),
-                RGBAColor(85, 68, 115, 1.0),
-                RGBAColor(123, 150, 224, 1.0),
-                RGBAColor(151, 42, 80, 1.0),
-                RGBAColor(187, 120, 110, 1.0),
-                RGBAColor(109, 118, 152, 1.0),
-                RGBAColor(172, 99, 170, 1.0),
-                RGBAColor(56, 99, 0, 1.0),
-                RGBAColor(209, 231, 224, 1.0),
-                RGBAColor(97, 168, 255, 1.0),
-                RGBAColor(170, 107, 112, 1.0),
-                RGBAColor(252, 133, 178, 1.0),
-                RGBAColor(0, 86, 178, 1.0),
-                RGBAColor(168, 174, 156, 1.0),
-                RGBAColor(255, 120, 106, 1.0),
-                RGBAColor(137, 114, 110, 1.0),
-            ],
+            colors: Cow::Borrowed(&RED_COLORS),
         };
+
+        /// Okabe-Ito colors, distinguishable with the common forms of color blindness
+        const CB_FRIENDLY_COLORS: [RGBAColor; 8] = [
+            RGBAColor(0, 114, 178, 1.0),
+            RGBAColor(230, 159, 0, 1.0),
+            RGBAColor(0, 158, 115, 1.0),
+            RGBAColor(213, 94, 0, 1.0),
+            RGBAColor(86, 180, 233, 1.0),
+            RGBAColor(204, 121, 167, 1.0),
+            RGBAColor(240, 228, 66, 1.0),
+            RGBAColor(0, 0, 0, 1.0),
+        ];
+
+        pub const CB_FRIENDLY_PALETTE: Palette = Palette {
+            background: RGBAColor(255, 255, 255, 1.0),
+            mesh: RGBAColor(200, 200, 200, 1.0),
+            colors: Cow::Borrowed(&CB_FRIENDLY_COLORS),
+        };
+
+        /// List of the built-in palettes
+        #[derive(EnumString, Display, Clone, Debug, PartialEq)]
+        pub enum PaletteEnum {
+            #[strum(ascii_case_insensitive)]
+            Red,
+            /// Colorblind-friendly palette
+            #[strum(serialize = "cb-friendly", ascii_case_insensitive)]
+            CbFriendly,
+        }
+
+        impl PaletteEnum {
+            /// Returns the palette of the variant
+            pub fn palette(&self) -> &'static Palette {
+                static RED: Palette = RED_PALETTE;
+                static CB_FRIENDLY: Palette = CB_FRIENDLY_PALETTE;
+                match self {
+                    PaletteEnum::Red => &RED,
+                    PaletteEnum::CbFriendly => &CB_FRIENDLY,
+                }
+            }
+        }
+
         /* pub const BLUE_PALETTE: Palette = Palette {
             background: RGBAColor(255, 255, 255, 1.0),
             mesh: RGBAColor(128, 128, 128, 1.0),
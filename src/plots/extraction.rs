@@ -2,54 +2,230 @@
 //!
 //! `extraction` is a colletion of utilities to extract information from a registry to make report plots
 //!
-use crate::model::registry::Registry;
-use chrono::{NaiveDate, Duration};
+use crate::model::{
+    registry::{
+        serialized_name, Registry, ACCOUNT_COLUMN, AMOUNT_COLUMN, CATEGORY_COLUMN, DATE_COLUMN,
+        DESCRIPTION_COLUMN,
+    },
+    transaction::{Flow, TransactionCategory},
+};
+use crate::plots::plot_errors::PlotError;
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
 use itertools::Itertools;
+use log::warn;
 use polars::lazy::dsl::col;
-use polars::prelude::*;
+use polars::prelude::{
+    lit, when, ChunkUnique, DataFrame, DataType, Expr, IntoLazy, NamedFrom, PolarsError, Series,
+    SortOptions,
+};
+use serde::Serialize;
 use std::{cmp::Ordering::Equal, collections::HashMap};
 
+#[derive(Serialize)]
 pub struct DailyTransactions {
     pub days: Vec<NaiveDate>,
-    pub amounts: Vec<f32>,
-    pub cumsum_amounts: Vec<f32>,
-    pub days_idx: Vec<f32>,
-    pub days_idx_range: (f32, f32),
-    pub amounts_range: (f32, f32),
-    pub cumsum_amounts_range: (f32, f32),
-    pub amounts_pairs: Vec<(f32, f32)>,
-    pub amount_cumulative_pairs: Vec<(f32, f32)>,
+    pub amounts: Vec<f64>,
+    pub cumsum_amounts: Vec<f64>,
+    pub days_idx: Vec<f64>,
+    pub days_idx_range: (f64, f64),
+    pub amounts_range: (f64, f64),
+    pub cumsum_amounts_range: (f64, f64),
+    pub amounts_pairs: Vec<(f64, f64)>,
+    pub amount_cumulative_pairs: Vec<(f64, f64)>,
 }
 
+/// Days when the balance is below a threshold
+pub struct LowBalanceDays {
+    pub days: Vec<NaiveDate>,
+    /// Balance at the end of each day
+    pub balances: Vec<f64>,
+}
+
+/// First day of the weeks of `Period::Week`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WeekStart {
+    /// Weeks start on Monday as the ISO weeks
+    #[default]
+    Monday,
+    Sunday,
+}
+
+/// Length of the periods used to group the transactions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week(WeekStart),
+    Month,
+    /// Fiscal years starting on the first day of the given month, 1 is January
+    /// and a year starting in April goes from April to the next March
+    Year(u32),
+}
+
+impl Period {
+    /// Duration of the period in the polars string language
+    fn duration(&self) -> &'static str {
+        match self {
+            Period::Day => "1d",
+            Period::Week(_) => "1w",
+            Period::Month => "1mo",
+            Period::Year(_) => "1y",
+        }
+    }
+
+    /// Checks that the start month of `Period::Year` is a month from 1 to 12
+    fn validate(&self) -> Result<(), PlotError> {
+        match self {
+            Period::Year(start) if !(1..=12).contains(start) => Err(PlotError::InvalidParameter(
+                format!("the fiscal year cannot start on month {start}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Truncates the dates of `date` to the first day of their period
+    ///
+    /// Polars weeks start on Monday and the truncation offset is added after
+    /// truncating, then for weeks starting on Sunday the dates are moved one day
+    /// forward before truncating and one day back with the offset.
+    /// Fiscal years are calendar years moved forward to their start month, the dates
+    /// before the start month belong to the fiscal year started the calendar year before.
+    fn truncate(&self, date: Expr) -> Expr {
+        match self {
+            Period::Year(start) if *start > 1 => when(date.clone().dt().month().lt(lit(*start)))
+                .then(
+                    date.clone()
+                        .dt()
+                        .truncate(self.duration(), &format!("-{}mo", 13 - start)),
+                )
+                .otherwise(
+                    date.dt()
+                        .truncate(self.duration(), &format!("{}mo", start - 1)),
+                ),
+            Period::Week(WeekStart::Sunday) => ((date.cast(DataType::Int32) + lit(1))
+                .cast(DataType::Date))
+            .dt()
+            .truncate(self.duration(), "-1d"),
+            _ => date.dt().truncate(self.duration(), "0"),
+        }
+    }
+}
+
+/// Net amount of the transactions of each period
+#[derive(Serialize)]
+pub struct NetByPeriod {
+    /// First day of each period with transactions, sorted
+    pub periods: Vec<NaiveDate>,
+    pub net_amounts: Vec<f64>,
+}
+
+/// Gross incomes and gross expenses of each month
+#[derive(Serialize)]
+pub struct MonthlyIncomeExpense {
+    /// First day of each month with transactions, sorted
+    pub months: Vec<NaiveDate>,
+    pub incomes: Vec<f64>,
+    /// Expenses as positive amounts
+    pub expenses: Vec<f64>,
+}
+
+/// Cumulative net amount of each account day by day
+pub struct AccountsCumulativeTransactions {
+    pub days: Vec<NaiveDate>,
+    /// Account names sorted alphabetically
+    pub accounts: Vec<String>,
+    /// Cumulative sum of each account with a value for each day
+    pub cumsum_amounts: Vec<Vec<f64>>,
+}
+
+#[derive(Serialize)]
 pub struct CategoriesSplit {
     pub income_categories: Vec<String>,
     pub income_percentages: Vec<f64>,
     pub income_amounts: Vec<f64>,
+    pub income_counts: Vec<usize>,
+    /// Mean absolute amount of the transactions of each income category
+    pub income_means: Vec<f64>,
     pub expense_categories: Vec<String>,
     pub expense_percentages: Vec<f64>,
     pub expense_amounts: Vec<f64>,
+    pub expense_counts: Vec<usize>,
+    /// Mean absolute amount of the transactions of each expense category
+    pub expense_means: Vec<f64>,
+    /// Number of transactions with a zero amount, they are on neither side
+    pub zero_count: usize,
 }
 
+#[derive(Serialize)]
 pub struct MonthlyTransactions {
     pub months: Vec<NaiveDate>,
-    pub net_income: Vec<f32>,
-    pub months_idx: Vec<f32>,
-    pub months_idx_range: (f32, f32),
-    pub net_income_range: (f32, f32),
-    pub net_income_pairs: Vec<(f32, f32)>,
+    pub net_income: Vec<f64>,
+    /// Sum of the incomes of each month, without the expenses
+    pub gross_income: Vec<f64>,
+    pub months_idx: Vec<f64>,
+    pub months_idx_range: (f64, f64),
+    pub net_income_range: (f64, f64),
+    pub net_income_pairs: Vec<(f64, f64)>,
     pub categories: Vec<String>,
-    pub categories_amounts: Vec<Vec<f32>>,
+    pub categories_amounts: Vec<Vec<f64>>,
     pub categories_months: Vec<Vec<NaiveDate>>,
-    pub categories_months_idx: Vec<Vec<f32>>,
-    pub categories_amounts_range: (f32, f32),
-    pub categories_months_idx_range: (f32, f32),
-    pub categories_pairs: Vec<Vec<(f32, f32)>>,
+    pub categories_months_idx: Vec<Vec<f64>>,
+    pub categories_amounts_range: (f64, f64),
+    pub categories_months_idx_range: (f64, f64),
+    pub categories_pairs: Vec<Vec<(f64, f64)>>,
     pub categories_amounts_perc: Vec<Vec<f64>>,
     pub categories_amounts_perc_value: Vec<Vec<f64>>,
     pub categories_amounts_perc_months: Vec<String>,
     pub categories_amounts_perc_names: Vec<Vec<String>>,
 }
 
+/// Monthly spending of each budgeted category compared to its budget
+pub struct BudgetComparison {
+    pub months: Vec<NaiveDate>,
+    pub categories: Vec<String>,
+    pub budgets: Vec<f64>,
+    /// Spending of each category per month as positive amounts
+    pub actuals: Vec<Vec<f64>>,
+    /// Budget minus spending of each category per month, negative when over budget
+    pub differences: Vec<Vec<f64>>,
+}
+
+/// Expenses of each day of the week in each month
+pub struct DowMonthMatrix {
+    /// First day of each month from the first to the last month with expenses
+    pub months: Vec<NaiveDate>,
+    /// Days of the week from Monday to Sunday
+    pub weekdays: Vec<Weekday>,
+    /// Expenses as positive amounts, with a row for each weekday and a column for each month
+    pub amounts: Vec<Vec<f64>>,
+}
+
+/// Expenses of each day of the week
+pub struct WeekdaySpending {
+    /// Days of the week from Monday to Sunday
+    pub weekdays: Vec<Weekday>,
+    /// Expenses as positive amounts, zero for the weekdays without expenses
+    pub amounts: Vec<f64>,
+}
+
+/// Linear trend of the monthly expenses of each category
+#[derive(Serialize)]
+pub struct CategoryTrends {
+    pub categories: Vec<String>,
+    /// Change of the monthly expenses per month, positive when the spending grows,
+    /// `None` for the categories with expenses in a single month
+    pub slopes: Vec<Option<f64>>,
+}
+
+/// Balance projected at the end of the month of the last day
+#[derive(Serialize)]
+pub struct BalanceProjection {
+    /// Last day of the month
+    pub date: NaiveDate,
+    pub balance: f64,
+    /// Mean daily change of the balance used by the projection
+    pub daily_delta: f64,
+}
+
 /// filter_registry returns registry as dataframe with applied filters
 ///
 /// ## Parameters
@@ -57,42 +233,56 @@ pub struct MonthlyTransactions {
 /// `registry`: Registry struct
 /// `accounts`: Optional parameter with a filter of the accounts to consider
 /// `date_range`: Optional parameter with a filter over the dates to consider
+/// `description_contains`: Optional parameter with a text that the descriptions
+/// must contain, ignoring the case
+/// `categories`: Optional parameter with a filter of the categories to consider
 fn filter_registry_df(
     registry: &Registry,
     accounts: Option<&Vec<String>>,
     date_range: Option<(&NaiveDate, &NaiveDate)>,
+    description_contains: Option<&str>,
+    categories: Option<&Vec<TransactionCategory>>,
 ) -> Result<DataFrame, Box<dyn std::error::Error>> {
-    let mut df = registry.to_dataframe()?.lazy();
+    let mut df = registry.filtered_dataframe(accounts, date_range)?.lazy();
 
-    if let Some(vector) = accounts {
-        let accounts = Series::new("account_list", vector);
-        df = df.filter(col("account").is_in(lit(accounts)));
-    }
-
-    if let Some((from, to)) = date_range {
+    if let Some(text) = description_contains {
         df = df.filter(
-            col("date")
-                .dt()
-                .strftime("%Y-%m-%d")
-                .gt_eq(lit(&from.to_string()[..])),
+            col(DESCRIPTION_COLUMN)
+                .str()
+                .to_lowercase()
+                .str()
+                .contains_literal(lit(text.to_lowercase())),
         );
-        df = df.filter(
-            col("date")
-                .dt()
-                .strftime("%Y-%m-%d")
-                .lt_eq(lit(&to.to_string()[..])),
+    }
+
+    if let Some(vector) = categories {
+        let categories = Series::new(
+            "category_list",
+            vector
+                .iter()
+                .map(serialized_name)
+                .collect::<Result<Vec<String>, _>>()?,
         );
+        df = df.filter(col(CATEGORY_COLUMN).is_in(lit(categories)));
     }
     let df = df.collect()?;
 
     Ok(df)
 }
 
+/// returns `PlotError::NoData` when the dataframe has no rows
+fn ensure_not_empty(df: &DataFrame) -> Result<(), PlotError> {
+    if df.height() == 0 {
+        return Err(PlotError::NoData);
+    }
+    Ok(())
+}
+
 /// returns a new dataframe with new rows for missing dates
 /// in the interval of dates of the original dataframe
 fn fill_missing_dates(df: DataFrame) -> DataFrame {
     let min_date: NaiveDate = df
-        .column("date")
+        .column(DATE_COLUMN)
         .unwrap()
         .date()
         .unwrap()
@@ -102,7 +292,7 @@ fn fill_missing_dates(df: DataFrame) -> DataFrame {
         .unwrap();
 
     let max_date: NaiveDate = df
-        .column("date")
+        .column(DATE_COLUMN)
         .unwrap()
         .date()
         .unwrap()
@@ -112,7 +302,7 @@ fn fill_missing_dates(df: DataFrame) -> DataFrame {
         .unwrap();
 
     let existing_dates: Vec<NaiveDate> = df
-        .column("date")
+        .column(DATE_COLUMN)
         .unwrap()
         .date()
         .unwrap()
@@ -123,19 +313,263 @@ fn fill_missing_dates(df: DataFrame) -> DataFrame {
     let mut date = min_date;
     let mut missing_dates: Vec<NaiveDate> = Vec::new();
     while !date.eq(&max_date) {
-        date = date + Duration::days(1);
+        date += Duration::days(1);
         if !existing_dates.contains(&date) {
             missing_dates.push(date)
         }
     }
     let len = missing_dates.len();
     let additional_rows = DataFrame::new(vec![
-        Series::new("date", missing_dates),
-        Series::new("amount", vec![0.0; len])
-    ]).unwrap();
+        Series::new(DATE_COLUMN, missing_dates),
+        Series::new(AMOUNT_COLUMN, vec![0.0; len]),
+    ])
+    .unwrap();
+
+    df.vstack(&additional_rows)
+        .unwrap()
+        .sort([DATE_COLUMN], false)
+        .unwrap()
+}
+
+/// returns the sum of the amounts of each period sorted by date
+///
+/// The dates are truncated to the first day of their period, hence the
+/// resulting dataframe has only the date and amount columns.
+fn net_by_period_df(df: DataFrame, period: Period) -> Result<DataFrame, PolarsError> {
+    df.lazy()
+        .with_column(period.truncate(col(DATE_COLUMN)))
+        .groupby([DATE_COLUMN])
+        .agg([col(AMOUNT_COLUMN).sum()])
+        .sort(
+            DATE_COLUMN,
+            SortOptions {
+                descending: false,
+                nulls_last: true,
+                multithreaded: true,
+            },
+        )
+        .collect()
+}
+
+/// returns the sum of the amounts of `flow` of each month sorted by date
+///
+/// The amounts of the other flows are set to zero instead of being removed so that
+/// every month with transactions is kept.
+fn monthly_flow_df(df: &DataFrame, flow: Flow) -> Result<DataFrame, PolarsError> {
+    net_by_period_df(
+        df.clone()
+            .lazy()
+            .with_column(
+                when(flow.filter(col(AMOUNT_COLUMN)))
+                    .then(col(AMOUNT_COLUMN))
+                    .otherwise(lit(0.0)),
+            )
+            .collect()?,
+        Period::Month,
+    )
+}
+
+/// Returns the net income as a percentage of the gross income
+///
+/// Returns `None` when there is no income, since the rate is undefined.
+pub fn savings_rate(net_income: f64, gross_income: f64) -> Option<f64> {
+    if gross_income > 0.0 {
+        Some(net_income / gross_income * 100.0)
+    } else {
+        None
+    }
+}
+
+/// Returns the slope of the least squares line through the points
+///
+/// Returns `None` when the slope is undefined, i.e. with less than two distinct x.
+pub fn linear_regression_slope(points: &[(f64, f64)]) -> Option<f64> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance > 0.0 {
+        Some(covariance / variance)
+    } else {
+        None
+    }
+}
 
-    df.vstack(&additional_rows).unwrap().sort(["date"], false).unwrap()
+/// extract_month_end_projection extrapolates the cumulative amounts to the end of the month
+///
+/// The mean daily delta of the last `window` days of `daily`, or of all of them for
+/// shorter series, is added to the last cumulative amount once for each day left
+/// until the end of the month of the last day.
+/// A zero window is a `PlotError::InvalidParameter`, a single day has no delta and
+/// is a `PlotError::NoData`.
+pub fn extract_month_end_projection(
+    daily: &DailyTransactions,
+    window: usize,
+) -> Result<BalanceProjection, PlotError> {
+    if window == 0 {
+        return Err(PlotError::InvalidParameter(String::from(
+            "the projection window needs at least one day",
+        )));
+    }
+    let n_days = daily.days.len().min(daily.cumsum_amounts.len());
+    if n_days < 2 {
+        return Err(PlotError::NoData);
+    }
+    // Missing days are filled by `extract_daily_transactions`, then the series is daily
+    let window = window.min(n_days - 1);
+    let last_balance = daily.cumsum_amounts[n_days - 1];
+    let daily_delta = (last_balance - daily.cumsum_amounts[n_days - 1 - window]) / window as f64;
 
+    let last_day = daily.days[n_days - 1];
+    let month_end = last_day.with_day(1).unwrap() + Months::new(1) - Duration::days(1);
+    let days_left = (month_end - last_day).num_days() as f64;
+    Ok(BalanceProjection {
+        date: month_end,
+        balance: last_balance + daily_delta * days_left,
+        daily_delta,
+    })
+}
+
+/// extract_category_trends fits a line to the monthly expenses of each category
+///
+/// The expenses of `monthly.categories_pairs` are negative, then their slope is
+/// negated so that a positive slope is a growing spending. Months without
+/// expenses in a category are not part of its fit.
+pub fn extract_category_trends(monthly: &MonthlyTransactions) -> CategoryTrends {
+    CategoryTrends {
+        categories: monthly.categories.clone(),
+        slopes: monthly
+            .categories_pairs
+            .iter()
+            .map(|pairs| linear_regression_slope(pairs).map(|slope| -slope))
+            .collect(),
+    }
+}
+
+/// Name of the category grouping the categories beyond `max_categories`
+pub const OTHER_CATEGORY: &str = "Other";
+
+/// keeps the first `max_categories` rows of a categories dataframe and
+/// groups the remaining ones into a single "Other" category
+///
+/// The dataframe is expected to have the `category`, `amount`, `count` and `mean`
+/// columns of `extract_categories_split`, sorted by relevance.
+fn group_other_categories(df: DataFrame, max_categories: usize) -> Result<DataFrame, PolarsError> {
+    if df.height() <= max_categories {
+        return Ok(df);
+    }
+    let tail = df.slice(max_categories as i64, df.height() - max_categories);
+    let other_amount: f64 = tail.column(AMOUNT_COLUMN)?.sum().unwrap_or(0.0);
+    let other_count: u32 = tail.column("count")?.sum().unwrap_or(0);
+    let other = DataFrame::new(vec![
+        Series::new(CATEGORY_COLUMN, &[OTHER_CATEGORY]),
+        Series::new(AMOUNT_COLUMN, &[other_amount]),
+        Series::new("count", &[other_count]),
+        // amounts of the same side have the same sign
+        Series::new("mean", &[other_amount.abs() / other_count as f64]),
+    ])?;
+    Ok(df.head(Some(max_categories)).vstack(&other)?.agg_chunks())
+}
+
+/// adds the `amount_perc` column to a categories dataframe of one side
+///
+/// Percentages are computed over the absolute total of the side so that
+/// they are positive and sum to 100 for both incomes and expenses.
+/// An empty side has no total to divide by, it gets an empty column.
+fn with_side_percentages(df: DataFrame) -> Result<DataFrame, PolarsError> {
+    if df.height() == 0 {
+        let mut df = df;
+        df.with_column(Series::new_empty("amount_perc", &DataType::Float64))?;
+        return Ok(df);
+    }
+    df.lazy()
+        .with_column(
+            (col(AMOUNT_COLUMN).abs() / col(AMOUNT_COLUMN).abs().sum() * lit(100.0))
+                .alias("amount_perc"),
+        )
+        .collect()
+}
+
+/// extract_net_by_period returns the net amount of the transactions of each period
+///
+/// ## Parameters
+///
+/// `registry`: Registry struct
+/// `period`: length of the periods, e.g. a month
+/// `accounts`: Optional parameter with a filter of the accounts to consider
+/// `date_range`: Optional parameter with a filter over the dates to consider
+pub fn extract_net_by_period(
+    registry: &Registry,
+    period: Period,
+    accounts: Option<&Vec<String>>,
+    date_range: Option<(&NaiveDate, &NaiveDate)>,
+) -> Result<NetByPeriod, Box<dyn std::error::Error>> {
+    period.validate()?;
+    let df = filter_registry_df(registry, accounts, date_range, None, None)?;
+    ensure_not_empty(&df)?;
+    let df = net_by_period_df(df, period)?;
+
+    let periods: Vec<NaiveDate> = df
+        .column(DATE_COLUMN)?
+        .date()?
+        .as_date_iter()
+        .flatten()
+        .collect();
+    let net_amounts: Vec<f64> = df
+        .column(AMOUNT_COLUMN)?
+        .f64()?
+        .into_iter()
+        .map(|x| x.unwrap_or(0.0))
+        .collect();
+
+    Ok(NetByPeriod {
+        periods,
+        net_amounts,
+    })
+}
+
+/// extract_monthly_income_expense returns the gross incomes and the gross expenses
+/// of each month, side by side instead of their net amount
+///
+/// ## Parameters
+///
+/// `registry`: Registry struct
+/// `accounts`: Optional parameter with a filter of the accounts to consider
+/// `date_range`: Optional parameter with a filter over the dates to consider
+pub fn extract_monthly_income_expense(
+    registry: &Registry,
+    accounts: Option<&Vec<String>>,
+    date_range: Option<(&NaiveDate, &NaiveDate)>,
+) -> Result<MonthlyIncomeExpense, Box<dyn std::error::Error>> {
+    let df = filter_registry_df(registry, accounts, date_range, None, None)?;
+    ensure_not_empty(&df)?;
+    let monthly_incomes = monthly_flow_df(&df, Flow::Income)?;
+    let monthly_expenses = monthly_flow_df(&df, Flow::Expense)?;
+
+    let months: Vec<NaiveDate> = monthly_incomes
+        .column(DATE_COLUMN)?
+        .date()?
+        .as_date_iter()
+        .flatten()
+        .collect();
+    let amounts = |df: &DataFrame| -> Result<Vec<f64>, PolarsError> {
+        Ok(df
+            .column(AMOUNT_COLUMN)?
+            .f64()?
+            .into_iter()
+            .map(|x| x.unwrap_or(0.0).abs())
+            .collect())
+    };
+
+    Ok(MonthlyIncomeExpense {
+        months,
+        incomes: amounts(&monthly_incomes)?,
+        expenses: amounts(&monthly_expenses)?,
+    })
 }
 
 /// extract_daily_transaction returns a tuple with two elements: a vector of dates
@@ -146,67 +580,76 @@ fn fill_missing_dates(df: DataFrame) -> DataFrame {
 /// `registry`: Registry struct
 /// `accounts`: Optional parameter with a filter of the accounts to consider
 /// `date_range`: Optional parameter with a filter over the dates to consider
+/// `description_contains`: Optional parameter with a text that the descriptions
+/// must contain, ignoring the case
+/// `categories`: Optional parameter with a filter of the categories to consider
 /// `with_initial_total_value`: bool, if true the initial value of the accouts
 /// in the registry cumulative amounts is added to the cumulative sum accounts
+/// `initial_value_override`: Optional starting value of the cumulative sum, when
+/// set it is used instead of the initial value of the accounts
 pub fn extract_daily_transactions(
     registry: &Registry,
     accounts: Option<&Vec<String>>,
     date_range: Option<(&NaiveDate, &NaiveDate)>,
+    description_contains: Option<&str>,
+    categories: Option<&Vec<TransactionCategory>>,
     with_initial_total_value: bool,
+    initial_value_override: Option<f32>,
 ) -> Result<DailyTransactions, Box<dyn std::error::Error>> {
-    let mut initial_total_value: f32 = 0.0;
-    if with_initial_total_value {
-        initial_total_value = registry.get_initial_account_values(accounts);
-    }
-
-    let df = filter_registry_df(registry, accounts, date_range)?;
-    let df = df
-        .lazy()
-        .groupby(["date"])
-        .agg([col("amount").sum()])
-        .sort(
-            "date",
-            SortOptions {
-                descending: false,
-                nulls_last: true,
-                multithreaded: true,
-            },
-        )
-        .collect()?;
+    let initial_total_value: f64 = match initial_value_override {
+        Some(value) => f64::from(value),
+        None if with_initial_total_value => {
+            f64::from(registry.get_initial_account_values(accounts))
+        }
+        None => 0.0,
+    };
 
+    let df = filter_registry_df(
+        registry,
+        accounts,
+        date_range,
+        description_contains,
+        categories,
+    )?;
+    ensure_not_empty(&df)?;
+    let df = net_by_period_df(df, Period::Day)?;
 
     // Add rows for missing dates and value equal to 0
     let df = fill_missing_dates(df);
-    let df = df.lazy().with_column(col("amount").cumsum(false).alias("amount_cumsum")).collect().unwrap();
+    let df = df
+        .lazy()
+        .with_column(col(AMOUNT_COLUMN).cumsum(false).alias("amount_cumsum"))
+        .collect()
+        .unwrap();
 
     let days: Vec<NaiveDate> = df
-        .column("date")
+        .column(DATE_COLUMN)
         .unwrap()
         .date()
         .unwrap()
         .as_date_iter()
         .map(|x| x.unwrap())
         .collect();
-    let amounts: Vec<f32> = df
-        .column("amount")
+    let amounts: Vec<f64> = df
+        .column(AMOUNT_COLUMN)
         .unwrap()
         .f64()
         .unwrap()
         .to_vec()
         .iter()
-        .map(|x| x.unwrap() as f32)
+        .map(|x| x.unwrap())
         .collect();
-    let cumsum_amounts: Vec<f32> = df
+    let cumsum_amounts: Vec<f64> = df
         .column("amount_cumsum")
         .unwrap()
         .f64()
         .unwrap()
         .to_vec()
         .iter()
-        .map(|x| (x.unwrap() as f32) + initial_total_value)
+        .map(|x| x.unwrap() + initial_total_value)
         .collect();
 
-    let days_idx: Vec<f32> = (0u8..=days.len() as u8).map(f32::from).collect();
+    let days_idx: Vec<f64> = (0..=days.len()).map(|x| x as f64).collect();
 
     // We take min and max to create plot boundaries
     let x_min = *days_idx
@@ -234,9 +677,9 @@ pub fn extract_daily_transactions(
         .max_by(|x, y| x.partial_cmp(y).unwrap_or(Equal))
         .unwrap();
 
-    let amounts_pairs: Vec<(f32, f32)> =
+    let amounts_pairs: Vec<(f64, f64)> =
         days_idx.clone().into_iter().zip(amounts.clone()).collect();
-    let amount_cumulative_pairs: Vec<(f32, f32)> = days_idx
+    let amount_cumulative_pairs: Vec<(f64, f64)> = days_idx
         .clone()
         .into_iter()
         .zip(cumsum_amounts.clone())
@@ -255,55 +698,223 @@ pub fn extract_daily_transactions(
     })
 }
 
+/// extract_low_balance_days returns the days when the balance is below the threshold
+///
+/// The balance is the cumulative sum of `extract_daily_transactions` starting from the
+/// initial values of the accounts, days without transactions keep the previous balance.
+///
+/// ## Parameters
+///
+/// `registry`: Registry struct
+/// `threshold`: balance under which a day is returned
+/// `accounts`: Optional parameter with a filter of the accounts to consider
+/// `date_range`: Optional parameter with a filter over the dates to consider
+pub fn extract_low_balance_days(
+    registry: &Registry,
+    threshold: f64,
+    accounts: Option<&Vec<String>>,
+    date_range: Option<(&NaiveDate, &NaiveDate)>,
+) -> Result<LowBalanceDays, Box<dyn std::error::Error>> {
+    let daily_transactions =
+        extract_daily_transactions(registry, accounts, date_range, None, None, true, None)?;
+
+    let (days, balances) = daily_transactions
+        .days
+        .into_iter()
+        .zip(daily_transactions.cumsum_amounts)
+        .filter(|(_, balance)| *balance < threshold)
+        .unzip();
+    Ok(LowBalanceDays { days, balances })
+}
+
+/// extract_accounts_cumulative returns the cumulative sum of the transactions of each account
+///
+/// Days are the same of `extract_daily_transactions`, so the cumulative sums of
+/// the accounts add up to its cumulative sum.
+///
+/// ## Parameters
+///
+/// `registry`: Registry struct
+/// `accounts`: Optional parameter with a filter of the accounts to consider
+/// `date_range`: Optional parameter with a filter over the dates to consider
+/// `description_contains`: Optional parameter with a text that the descriptions
+/// must contain, ignoring the case
+/// `with_initial_total_value`: bool, if true the initial value of each account
+/// is added to its cumulative sum
+pub fn extract_accounts_cumulative(
+    registry: &Registry,
+    accounts: Option<&Vec<String>>,
+    date_range: Option<(&NaiveDate, &NaiveDate)>,
+    description_contains: Option<&str>,
+    with_initial_total_value: bool,
+) -> Result<AccountsCumulativeTransactions, Box<dyn std::error::Error>> {
+    let df = filter_registry_df(registry, accounts, date_range, description_contains, None)?;
+    ensure_not_empty(&df)?;
+
+    let daily_df = net_by_period_df(df.clone(), Period::Day)?;
+    let days: Vec<NaiveDate> = fill_missing_dates(daily_df)
+        .column(DATE_COLUMN)?
+        .date()?
+        .as_date_iter()
+        .map(|x| x.unwrap())
+        .collect();
+    let days_mapping: HashMap<NaiveDate, usize> =
+        days.iter().enumerate().map(|(i, day)| (*day, i)).collect();
+
+    let account_column = df.column(ACCOUNT_COLUMN)?.cast(&DataType::Utf8)?;
+    let account_names: Vec<String> = account_column
+        .utf8()?
+        .into_iter()
+        .flatten()
+        .map(String::from)
+        .unique()
+        .sorted()
+        .collect();
+
+    let mut daily_amounts = vec![vec![0.0; days.len()]; account_names.len()];
+    for ((day, account), amount) in df
+        .column(DATE_COLUMN)?
+        .date()?
+        .as_date_iter()
+        .zip(account_column.utf8()?)
+        .zip(df.column(AMOUNT_COLUMN)?.f64()?)
+    {
+        if let (Some(day), Some(account), Some(amount)) = (day, account, amount) {
+            let account_idx = account_names.iter().position(|x| x == account).unwrap();
+            daily_amounts[account_idx][days_mapping[&day]] += amount;
+        }
+    }
+
+    let cumsum_amounts: Vec<Vec<f64>> = account_names
+        .iter()
+        .zip(daily_amounts)
+        .map(|(account, amounts)| {
+            let initial_value = if with_initial_total_value {
+                f64::from(registry.get_initial_account_values(Some(&vec![account.clone()])))
+            } else {
+                0.0
+            };
+            amounts
+                .iter()
+                .scan(initial_value, |total, amount| {
+                    *total += amount;
+                    Some(*total)
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(AccountsCumulativeTransactions {
+        days,
+        accounts: account_names,
+        cumsum_amounts,
+    })
+}
+
+/// extract_categories_split returns the amounts and percentages of each category
+/// split between incomes and expenses
+///
+/// Amounts keep the sign of the transactions, hence incomes are positive and
+/// expenses are negative. Percentages are always positive and are computed
+/// over the absolute total of their side.
+/// Transactions with a zero amount, like void entries, are on neither side, see
+/// `Flow::Zero`, then they are counted in `zero_count` and logged with a warning.
+///
+/// ## Parameters
+///
+/// `registry`: Registry struct
+/// `accounts`: Optional parameter with a filter of the accounts to consider
+/// `date_range`: Optional parameter with a filter over the dates to consider
+/// `description_contains`: Optional parameter with a text that the descriptions
+/// must contain, ignoring the case
+/// `categories`: Optional parameter with a filter of the categories to consider
+/// `max_categories`: Optional maximum number of categories per side, the remaining
+/// ones are grouped into the "Other" category
 pub fn extract_categories_split(
     registry: &Registry,
     accounts: Option<&Vec<String>>,
     date_range: Option<(&NaiveDate, &NaiveDate)>,
+    description_contains: Option<&str>,
     max_categories: Option<usize>,
 ) -> Result<CategoriesSplit, Box<dyn std::error::Error>> {
-    let df = filter_registry_df(registry, accounts, date_range)?;
+    let df = filter_registry_df(registry, accounts, date_range, description_contains, None)?;
+    ensure_not_empty(&df)?;
 
     let mut incomes = df
         .clone()
         .lazy()
-        .filter(col("amount").gt(0.0))
-        .groupby(["category"])
-        .agg([col("amount").sum()])
+        .filter(Flow::Income.filter(col(AMOUNT_COLUMN)))
+        .groupby([CATEGORY_COLUMN])
+        .agg([
+            col(AMOUNT_COLUMN).sum(),
+            col(AMOUNT_COLUMN).count().alias("count"),
+            col(AMOUNT_COLUMN).abs().mean().alias("mean"),
+        ])
+        .select([
+            col(CATEGORY_COLUMN).cast(DataType::Utf8),
+            col(AMOUNT_COLUMN).cast(DataType::Float64),
+            col("count").cast(DataType::UInt32),
+            col("mean").cast(DataType::Float64),
+        ])
         .sort(
-            "amount",
+            AMOUNT_COLUMN,
             SortOptions {
-                descending: false,
+                descending: true,
                 nulls_last: true,
                 multithreaded: true,
             },
         )
-        .with_column((col("amount") / col("amount").sum() * lit(100.0)).alias("amount_perc"))
         .collect()?;
 
+    let zero_count = df
+        .clone()
+        .lazy()
+        .filter(Flow::Zero.filter(col(AMOUNT_COLUMN)))
+        .collect()?
+        .height();
+    if zero_count > 0 {
+        warn!(
+            "{} transactions with a zero amount are excluded from the categories split",
+            zero_count
+        );
+    }
+
     let mut expenses = df
         .lazy()
-        .filter(col("amount").lt(0.0))
-        .groupby(["category"])
-        .agg([col("amount").sum()])
+        .filter(Flow::Expense.filter(col(AMOUNT_COLUMN)))
+        .groupby([CATEGORY_COLUMN])
+        .agg([
+            col(AMOUNT_COLUMN).sum(),
+            col(AMOUNT_COLUMN).count().alias("count"),
+            col(AMOUNT_COLUMN).abs().mean().alias("mean"),
+        ])
+        .select([
+            col(CATEGORY_COLUMN).cast(DataType::Utf8),
+            col(AMOUNT_COLUMN).cast(DataType::Float64),
+            col("count").cast(DataType::UInt32),
+            col("mean").cast(DataType::Float64),
+        ])
         .sort(
-            "amount",
+            AMOUNT_COLUMN,
             SortOptions {
                 descending: false,
                 nulls_last: true,
                 multithreaded: true,
             },
         )
-        .with_column((col("amount") / col("amount").sum() * lit(100.0)).alias("amount_perc"))
         .collect()?;
 
     if let Some(num) = max_categories {
-        incomes = incomes.head(Some(num));
-        expenses = expenses.head(Some(num))
+        incomes = group_other_categories(incomes, num)?;
+        expenses = group_other_categories(expenses, num)?;
     }
 
+    let incomes = with_side_percentages(incomes)?;
+    let expenses = with_side_percentages(expenses)?;
+
     Ok(CategoriesSplit {
         income_categories: incomes
-            .column("category")
+            .column(CATEGORY_COLUMN)
             .unwrap()
             .iter()
             .map(|x| x.to_owned().to_string().replace('\"', ""))
@@ -318,7 +929,25 @@ pub fn extract_categories_split(
             .map(|x| x.unwrap())
             .collect(),
         income_amounts: incomes
-            .column("amount")
+            .column(AMOUNT_COLUMN)
+            .unwrap()
+            .f64()
+            .unwrap()
+            .to_vec()
+            .iter()
+            .map(|x| x.unwrap())
+            .collect(),
+        income_counts: incomes
+            .column("count")
+            .unwrap()
+            .u32()
+            .unwrap()
+            .to_vec()
+            .iter()
+            .map(|x| x.unwrap() as usize)
+            .collect(),
+        income_means: incomes
+            .column("mean")
             .unwrap()
             .f64()
             .unwrap()
@@ -327,7 +956,7 @@ pub fn extract_categories_split(
             .map(|x| x.unwrap())
             .collect(),
         expense_categories: expenses
-            .column("category")
+            .column(CATEGORY_COLUMN)
             .unwrap()
             .iter()
             .map(|x| x.to_owned().to_string().replace('\"', ""))
@@ -342,7 +971,7 @@ pub fn extract_categories_split(
             .map(|x| x.unwrap())
             .collect(),
         expense_amounts: expenses
-            .column("amount")
+            .column(AMOUNT_COLUMN)
             .unwrap()
             .f64()
             .unwrap()
@@ -350,43 +979,79 @@ pub fn extract_categories_split(
             .iter()
             .map(|x| x.unwrap())
             .collect(),
+        expense_counts: expenses
+            .column("count")
+            .unwrap()
+            .u32()
+            .unwrap()
+            .to_vec()
+            .iter()
+            .map(|x| x.unwrap() as usize)
+            .collect(),
+        expense_means: expenses
+            .column("mean")
+            .unwrap()
+            .f64()
+            .unwrap()
+            .to_vec()
+            .iter()
+            .map(|x| x.unwrap())
+            .collect(),
+        zero_count,
     })
 }
 
+/// monthy_extraction returns the monthly net income and the monthly expenses
+/// of each category
+///
+/// ## Parameters
+///
+/// `registry`: Registry struct
+/// `accounts`: Optional parameter with a filter of the accounts to consider
+/// `date_range`: Optional parameter with a filter over the dates to consider
+/// `description_contains`: Optional parameter with a text that the descriptions
+/// must contain, ignoring the case
+/// `categories`: Optional parameter with a filter of the categories to consider
+/// `max_categories`: Optional parameter with the number of categories of each month
+/// `category_order`: Optional parameter with the display order of the categories,
+/// the ones missing from it follow sorted by name
 pub fn monthy_extraction(
     registry: &Registry,
     accounts: Option<&Vec<String>>,
     date_range: Option<(&NaiveDate, &NaiveDate)>,
+    description_contains: Option<&str>,
+    categories: Option<&Vec<TransactionCategory>>,
     max_categories: Option<usize>,
+    category_order: Option<&[TransactionCategory]>,
 ) -> Result<MonthlyTransactions, Box<dyn std::error::Error>> {
-    let df = filter_registry_df(registry, accounts, date_range)?;
+    let df = filter_registry_df(
+        registry,
+        accounts,
+        date_range,
+        description_contains,
+        categories,
+    )?;
+    ensure_not_empty(&df)?;
+    monthly_transactions_from_df(df, max_categories, category_order)
+}
 
-    let monthy_net_income = df
-        .clone()
-        .lazy()
-        .with_column(col("date").alias("year-month").dt().truncate("1mo", "1"))
-        .groupby(["year-month"])
-        .agg([col("amount").sum()])
-        .sort(
-            "year-month",
-            SortOptions {
-                descending: false,
-                nulls_last: true,
-                multithreaded: true,
-            },
-        )
-        .collect()
-        .unwrap();
+/// Builds the monthly transactions from the filtered registry dataframe
+///
+/// Errors of the dataframe operations are returned instead of panicking.
+fn monthly_transactions_from_df(
+    df: DataFrame,
+    max_categories: Option<usize>,
+    category_order: Option<&[TransactionCategory]>,
+) -> Result<MonthlyTransactions, Box<dyn std::error::Error>> {
+    let monthy_net_income = net_by_period_df(df.clone(), Period::Month)?;
 
     let months: Vec<NaiveDate> = monthy_net_income
-        .column("year-month")
-        .unwrap()
-        .date()
-        .unwrap()
+        .column(DATE_COLUMN)?
+        .date()?
         .as_date_iter()
         .map(|x| x.unwrap())
         .collect();
-    let months_idx: Vec<f32> = (0u8..months.len() as u8).map(f32::from).collect();
+    let months_idx: Vec<f64> = (0..months.len()).map(|x| x as f64).collect();
     let months_idx_range = (
         *months_idx
             .iter()
@@ -398,14 +1063,12 @@ pub fn monthy_extraction(
             .unwrap(),
     );
 
-    let net_income: Vec<f32> = monthy_net_income
-        .column("amount")
-        .unwrap()
-        .f64()
-        .unwrap()
+    let net_income: Vec<f64> = monthy_net_income
+        .column(AMOUNT_COLUMN)?
+        .f64()?
         .to_vec()
         .iter()
-        .map(|x| x.unwrap() as f32)
+        .map(|x| x.unwrap())
         .collect();
     let net_income_range = (
         *net_income
@@ -417,19 +1080,33 @@ pub fn monthy_extraction(
             .max_by(|x, y| x.partial_cmp(y).unwrap_or(Equal))
             .unwrap(),
     );
-    let net_income_pairs: Vec<(f32, f32)> = months_idx
+    let net_income_pairs: Vec<(f64, f64)> = months_idx
         .clone()
         .into_iter()
         .zip(net_income.clone())
         .collect();
 
+    let gross_income: Vec<f64> = monthly_flow_df(&df, Flow::Income)?
+        .column(AMOUNT_COLUMN)?
+        .f64()?
+        .into_iter()
+        .map(|x| x.unwrap_or(0.0))
+        .collect();
+
     let expenses_per_category = df
         .lazy()
-        .filter(col("amount").lt(0.0))
-        .with_column(col("date").alias("year-month").dt().truncate("1mo", "1"))
-        .groupby(["year-month", "category"])
-        .agg([col("amount").sum()])
-        .with_column((col("amount") / col("amount").sum() * lit(100.0)).alias("amount_perc"))
+        .filter(Flow::Expense.filter(col(AMOUNT_COLUMN)))
+        .with_column(
+            col(DATE_COLUMN)
+                .alias("year-month")
+                .dt()
+                .truncate("1mo", "1"),
+        )
+        .groupby(["year-month", CATEGORY_COLUMN])
+        .agg([col(AMOUNT_COLUMN).sum()])
+        .with_column(
+            (col(AMOUNT_COLUMN) / col(AMOUNT_COLUMN).sum() * lit(100.0)).alias("amount_perc"),
+        )
         .sort(
             "year-month",
             SortOptions {
@@ -438,55 +1115,62 @@ pub fn monthy_extraction(
                 multithreaded: true,
             },
         )
-        .collect()
-        .unwrap();
+        .collect()?;
 
     let mut categories: Vec<String> = Vec::new();
     let mut categories_months: Vec<Vec<NaiveDate>> = Vec::new();
-    let mut categories_months_idx: Vec<Vec<f32>> = Vec::new();
-    let mut categories_amounts: Vec<Vec<f32>> = Vec::new();
-    let mut categories_amounts_min: Option<f32> = None;
-    let mut categories_amounts_max: Option<f32> = None;
-    let mut categories_pairs: Vec<Vec<(f32, f32)>> = Vec::new();
-    let categories_months_idx_min: f32 = 0.0;
-    let categories_months_idx_max: f32 = months_idx_range.1;
-    let mut months_idx_mapping: HashMap<&NaiveDate, f32> = HashMap::new();
+    let mut categories_months_idx: Vec<Vec<f64>> = Vec::new();
+    let mut categories_amounts: Vec<Vec<f64>> = Vec::new();
+    let mut categories_amounts_min: Option<f64> = None;
+    let mut categories_amounts_max: Option<f64> = None;
+    let mut categories_pairs: Vec<Vec<(f64, f64)>> = Vec::new();
+    let categories_months_idx_min: f64 = 0.0;
+    let categories_months_idx_max: f64 = months_idx_range.1;
+    let mut months_idx_mapping: HashMap<&NaiveDate, f64> = HashMap::new();
     for (i, month) in months.iter().enumerate() {
-        months_idx_mapping.insert(month, i as f32);
+        months_idx_mapping.insert(month, i as f64);
     }
 
-    for category in expenses_per_category
-        .column("category")
-        .unwrap()
-        .utf8()
-        .unwrap()
-        .unique()
-        .unwrap()
+    let order: Vec<String> = category_order
+        .unwrap_or_default()
+        .iter()
+        .map(serialized_name)
+        .collect::<Result<_, _>>()?;
+    let mut expense_categories: Vec<String> = expenses_per_category
+        .column(CATEGORY_COLUMN)?
+        .utf8()?
+        .unique()?
         .into_iter()
         .map(|f| String::from(f.unwrap()))
-    {
+        .collect();
+    expense_categories.sort_by_cached_key(|category| {
+        (
+            order
+                .iter()
+                .position(|x| x == category)
+                .unwrap_or(order.len()),
+            category.clone(),
+        )
+    });
+
+    for category in expense_categories {
         let cat_df = expenses_per_category
             .clone()
             .lazy()
-            .filter(col("category").eq(lit(&category[..])))
-            .collect()
-            .unwrap();
+            .filter(col(CATEGORY_COLUMN).eq(lit(&category[..])))
+            .collect()?;
         let xs: Vec<NaiveDate> = cat_df
-            .column("year-month")
-            .unwrap()
-            .date()
-            .unwrap()
+            .column("year-month")?
+            .date()?
             .as_date_iter()
             .map(|x| x.unwrap())
             .collect();
-        let ys: Vec<f32> = cat_df
-            .column("amount")
-            .unwrap()
-            .f64()
-            .unwrap()
+        let ys: Vec<f64> = cat_df
+            .column(AMOUNT_COLUMN)?
+            .f64()?
             .to_vec()
             .iter()
-            .map(|x| x.unwrap() as f32)
+            .map(|x| x.unwrap())
             .collect();
 
         categories_amounts_min = match categories_amounts_min {
@@ -525,7 +1209,7 @@ pub fn monthy_extraction(
                     .unwrap(),
             ),
         };
-        let xs_idx_local: Vec<f32> = xs
+        let xs_idx_local: Vec<f64> = xs
             .clone()
             .iter()
             .map(|x| *months_idx_mapping.get(x).unwrap())
@@ -547,7 +1231,7 @@ pub fn monthy_extraction(
         let mut month_df = expenses_per_category
             .clone()
             .lazy()
-            //.filter(col("category").is_in(lit(Series::new("categories", categories.clone()))))
+            //.filter(col(CATEGORY_COLUMN).is_in(lit(Series::new("categories", categories.clone()))))
             .filter(
                 col("year-month")
                     .dt()
@@ -562,37 +1246,30 @@ pub fn monthy_extraction(
                     multithreaded: true,
                 },
             )
-            .collect()
-            .unwrap();
+            .collect()?;
         if max_categories.is_some() {
             month_df = month_df.head(max_categories);
         }
 
         let percs: Vec<f64> = month_df
-            .column("amount_perc")
-            .unwrap()
-            .f64()
-            .unwrap()
+            .column("amount_perc")?
+            .f64()?
             //.to_vec().iter().map(|x| x.unwrap().abs().log(10.0) as f32) logarithmic
             .to_vec()
             .iter()
             .map(|x| x.unwrap())
             .collect();
         let amounts: Vec<f64> = month_df
-            .column("amount")
-            .unwrap()
-            .f64()
-            .unwrap()
+            .column(AMOUNT_COLUMN)?
+            .f64()?
             //.to_vec().iter().map(|x| x.unwrap().abs().log(10.0) as f32) logarithmic
             .to_vec()
             .iter()
             .map(|x| x.unwrap())
             .collect();
         let cats: Vec<String> = month_df
-            .column("category")
-            .unwrap()
-            .utf8()
-            .unwrap()
+            .column(CATEGORY_COLUMN)?
+            .utf8()?
             .into_iter()
             .map(|f| String::from(f.unwrap()))
             .collect();
@@ -603,12 +1280,13 @@ pub fn monthy_extraction(
         categories_amounts_perc_names.push(cats);
     }
 
-    let categories_amounts_min = categories_amounts_min.unwrap();
-    let categories_amounts_max = categories_amounts_max.unwrap();
+    let categories_amounts_min = categories_amounts_min.unwrap_or(0.0);
+    let categories_amounts_max = categories_amounts_max.unwrap_or(0.0);
 
     Ok(MonthlyTransactions {
         months,
         net_income,
+        gross_income,
         months_idx,
         months_idx_range,
         net_income_range,
@@ -626,3 +1304,188 @@ pub fn monthy_extraction(
         categories_amounts_perc_names,
     })
 }
+
+/// extract_budget_comparison compares the monthly expenses of each category with its budget
+///
+/// Categories are named and sorted by their serialized name and months without
+/// expenses of a category count as zero spending.
+///
+/// ## Parameters
+///
+/// `registry`: Registry struct
+/// `budgets`: monthly budget for each category as positive amount
+/// `accounts`: Optional parameter with a filter of the accounts to consider
+/// `date_range`: Optional parameter with a filter over the dates to consider
+/// `description_contains`: Optional parameter with a text that the descriptions
+/// must contain, ignoring the case
+pub fn extract_budget_comparison(
+    registry: &Registry,
+    budgets: &HashMap<TransactionCategory, f32>,
+    accounts: Option<&Vec<String>>,
+    date_range: Option<(&NaiveDate, &NaiveDate)>,
+    description_contains: Option<&str>,
+) -> Result<BudgetComparison, Box<dyn std::error::Error>> {
+    let monthly_transactions = monthy_extraction(
+        registry,
+        accounts,
+        date_range,
+        description_contains,
+        None,
+        None,
+        None,
+    )?;
+
+    let mut budgets: Vec<(String, f64)> = budgets
+        .iter()
+        .map(|(category, budget)| Ok((serialized_name(category)?, f64::from(*budget))))
+        .collect::<Result<_, serde_json::Error>>()?;
+    budgets.sort_by(|x, y| x.0.cmp(&y.0));
+
+    let mut actuals: Vec<Vec<f64>> = Vec::new();
+    let mut differences: Vec<Vec<f64>> = Vec::new();
+    for (category, budget) in budgets.iter() {
+        let mut spent_per_month: HashMap<&NaiveDate, f64> = HashMap::new();
+        if let Some(i) = monthly_transactions
+            .categories
+            .iter()
+            .position(|x| x == category)
+        {
+            for (month, amount) in monthly_transactions.categories_months[i]
+                .iter()
+                .zip(&monthly_transactions.categories_amounts[i])
+            {
+                spent_per_month.insert(month, -amount);
+            }
+        }
+        let category_actuals: Vec<f64> = monthly_transactions
+            .months
+            .iter()
+            .map(|month| *spent_per_month.get(month).unwrap_or(&0.0))
+            .collect();
+        differences.push(category_actuals.iter().map(|x| budget - x).collect());
+        actuals.push(category_actuals);
+    }
+
+    Ok(BudgetComparison {
+        months: monthly_transactions.months,
+        categories: budgets.iter().map(|x| x.0.clone()).collect(),
+        budgets: budgets.iter().map(|x| x.1).collect(),
+        actuals,
+        differences,
+    })
+}
+
+/// extract_dow_month_matrix sums the expenses by day of the week and month
+///
+/// Months without expenses between the first and the last one are kept
+/// with zero amounts.
+///
+/// ## Parameters
+///
+/// `registry`: Registry struct
+/// `accounts`: Optional parameter with a filter of the accounts to consider
+/// `date_range`: Optional parameter with a filter over the dates to consider
+/// `description_contains`: Optional parameter with a text that the descriptions
+/// must contain, ignoring the case
+pub fn extract_dow_month_matrix(
+    registry: &Registry,
+    accounts: Option<&Vec<String>>,
+    date_range: Option<(&NaiveDate, &NaiveDate)>,
+    description_contains: Option<&str>,
+) -> Result<DowMonthMatrix, Box<dyn std::error::Error>> {
+    let df = filter_registry_df(registry, accounts, date_range, description_contains, None)?
+        .lazy()
+        .filter(Flow::Expense.filter(col(AMOUNT_COLUMN)))
+        .collect()?;
+    ensure_not_empty(&df)?;
+
+    let expenses: Vec<(NaiveDate, f64)> = df
+        .column(DATE_COLUMN)?
+        .date()?
+        .as_date_iter()
+        .zip(df.column(AMOUNT_COLUMN)?.f64()?)
+        .filter_map(|(date, amount)| Some((date?, -amount?)))
+        .collect();
+
+    let first_month = |date: &NaiveDate| date.with_day(1).unwrap();
+    let start = expenses.iter().map(|x| first_month(&x.0)).min().unwrap();
+    let end = expenses.iter().map(|x| first_month(&x.0)).max().unwrap();
+    let mut months: Vec<NaiveDate> = vec![start];
+    while *months.last().unwrap() < end {
+        let last = months.last().unwrap();
+        months.push(if last.month() == 12 {
+            NaiveDate::from_ymd_opt(last.year() + 1, 1, 1).unwrap()
+        } else {
+            NaiveDate::from_ymd_opt(last.year(), last.month() + 1, 1).unwrap()
+        });
+    }
+
+    let mut amounts = vec![vec![0.0; months.len()]; 7];
+    for (date, amount) in expenses {
+        let month_idx = months.binary_search(&first_month(&date)).unwrap();
+        amounts[date.weekday().num_days_from_monday() as usize][month_idx] += amount;
+    }
+
+    Ok(DowMonthMatrix {
+        months,
+        weekdays: vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ],
+        amounts,
+    })
+}
+
+/// extract_weekday_spending sums the expenses by day of the week
+///
+/// ## Parameters
+///
+/// `registry`: Registry struct
+/// `accounts`: Optional parameter with a filter of the accounts to consider
+/// `date_range`: Optional parameter with a filter over the dates to consider
+/// `description_contains`: Optional parameter with a text that the descriptions
+/// must contain, ignoring the case
+pub fn extract_weekday_spending(
+    registry: &Registry,
+    accounts: Option<&Vec<String>>,
+    date_range: Option<(&NaiveDate, &NaiveDate)>,
+    description_contains: Option<&str>,
+) -> Result<WeekdaySpending, Box<dyn std::error::Error>> {
+    let matrix = extract_dow_month_matrix(registry, accounts, date_range, description_contains)?;
+    Ok(WeekdaySpending {
+        weekdays: matrix.weekdays,
+        amounts: matrix.amounts.iter().map(|row| row.iter().sum()).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use polars::prelude::*;
+
+    use crate::model::registry::{
+        ACCOUNT_COLUMN, AMOUNT_COLUMN, CATEGORY_COLUMN, DATE_COLUMN, DESCRIPTION_COLUMN,
+    };
+
+    use super::monthly_transactions_from_df;
+
+    #[test]
+    fn monthly_polars_error() {
+        // The categories are numbers instead of strings
+        let df = DataFrame::new(vec![
+            Series::new(DATE_COLUMN, &[NaiveDate::from_ymd_opt(2023, 5, 9).unwrap()]),
+            Series::new(AMOUNT_COLUMN, &[-32.0]),
+            Series::new(CATEGORY_COLUMN, &[1]),
+            Series::new(DESCRIPTION_COLUMN, &[None::<&str>]),
+            Series::new(ACCOUNT_COLUMN, &["Ale"]),
+        ])
+        .unwrap();
+
+        assert!(monthly_transactions_from_df(df, None, None).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! # Figure Backend
+//!
+//! Bitmap drawing backend with an alpha channel. The figures drawn without a
+//! background are transparent and their antialiased edges are blended with the
+//! transparency instead of a background color.
+
+use image::{DynamicImage, ImageError, ImageFormat, Rgba, RgbaImage};
+use plotters::prelude::DrawingBackend;
+use plotters_backend::{BackendColor, BackendCoord, BackendStyle, DrawingErrorKind};
+
+/// Drawing backend of the figures saved by the plots
+///
+/// The pixels start transparent and each drawing is composited over them, the
+/// figure is saved by `present` in the format of the path extension. Formats
+/// without an alpha channel, like jpeg, are saved without it.
+pub(crate) struct FigureBackend {
+    path: String,
+    buffer: RgbaImage,
+}
+
+impl FigureBackend {
+    /// Create a transparent figure of `size` pixels saved in `path`
+    pub(crate) fn new(path: &str, size: (u32, u32)) -> FigureBackend {
+        FigureBackend {
+            path: String::from(path),
+            buffer: RgbaImage::new(size.0, size.1),
+        }
+    }
+
+    /// Draws `color` over the pixel at `x`, `y` with the "over" operator
+    fn blend(&mut self, x: u32, y: u32, color: BackendColor) {
+        let alpha = color.alpha.clamp(0.0, 1.0);
+        if alpha == 0.0 {
+            return;
+        }
+        let pixel = self.buffer.get_pixel_mut(x, y);
+        let below_alpha = f64::from(pixel.0[3]) / 255.0;
+        let out_alpha = alpha + below_alpha * (1.0 - alpha);
+        let channel = |above: u8, below: u8| -> u8 {
+            let value = (f64::from(above) * alpha + f64::from(below) * below_alpha * (1.0 - alpha))
+                / out_alpha;
+            value.round() as u8
+        };
+        *pixel = Rgba([
+            channel(color.rgb.0, pixel.0[0]),
+            channel(color.rgb.1, pixel.0[1]),
+            channel(color.rgb.2, pixel.0[2]),
+            (out_alpha * 255.0).round() as u8,
+        ]);
+    }
+}
+
+impl DrawingBackend for FigureBackend {
+    type ErrorType = ImageError;
+
+    fn get_size(&self) -> (u32, u32) {
+        self.buffer.dimensions()
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<ImageError>> {
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<ImageError>> {
+        let figure = DynamicImage::ImageRgba8(self.buffer.clone());
+        let figure = match ImageFormat::from_path(&self.path) {
+            Ok(ImageFormat::Jpeg) => DynamicImage::ImageRgb8(figure.to_rgb8()),
+            _ => figure,
+        };
+        figure
+            .save(&self.path)
+            .map_err(DrawingErrorKind::DrawingError)
+    }
+
+    fn draw_pixel(
+        &mut self,
+        point: BackendCoord,
+        color: BackendColor,
+    ) -> Result<(), DrawingErrorKind<ImageError>> {
+        let (width, height) = self.buffer.dimensions();
+        if let (Ok(x), Ok(y)) = (u32::try_from(point.0), u32::try_from(point.1)) {
+            if x < width && y < height {
+                self.blend(x, y, color);
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<ImageError>> {
+        if !fill {
+            return plotters_backend::rasterizer::draw_rect(
+                self,
+                upper_left,
+                bottom_right,
+                style,
+                fill,
+            );
+        }
+        // filled rectangles, like the backgrounds, are blended without the rasterizer
+        let (width, height) = self.buffer.dimensions();
+        let clamp = |value: i32, max: u32| value.clamp(0, max as i32) as u32;
+        let color = style.color();
+        for y in clamp(upper_left.1, height)..clamp(bottom_right.1 + 1, height) {
+            for x in clamp(upper_left.0, width)..clamp(bottom_right.0 + 1, width) {
+                self.blend(x, y, color);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plotters::prelude::DrawingBackend;
+    use plotters_backend::BackendColor;
+
+    use super::FigureBackend;
+
+    #[test]
+    fn blend_over_transparency() {
+        let mut backend = FigureBackend::new("figure.png", (2, 1));
+        let half_red = BackendColor {
+            alpha: 0.5,
+            rgb: (255, 0, 0),
+        };
+        backend.draw_pixel((0, 0), half_red).unwrap();
+        // the color is kept, only its alpha is reduced
+        assert_eq!(backend.buffer.get_pixel(0, 0).0, [255, 0, 0, 128]);
+        assert_eq!(backend.buffer.get_pixel(1, 0).0, [0, 0, 0, 0]);
+
+        let blue = BackendColor {
+            alpha: 1.0,
+            rgb: (0, 0, 255),
+        };
+        backend.draw_pixel((0, 0), half_red).unwrap();
+        backend.draw_pixel((1, 0), blue).unwrap();
+        backend.draw_pixel((1, 0), half_red).unwrap();
+        assert_eq!(backend.buffer.get_pixel(0, 0).0, [255, 0, 0, 192]);
+        assert_eq!(backend.buffer.get_pixel(1, 0).0, [128, 0, 128, 255]);
+
+        // pixels outside of the figure are ignored
+        backend.draw_pixel((-1, 0), blue).unwrap();
+        backend.draw_pixel((2, 0), blue).unwrap();
+    }
+}
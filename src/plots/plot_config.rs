@@ -0,0 +1,214 @@
+//! # Plot Config
+//!
+//! Options shared by the plot functions, built from the defaults with chained setters.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use plotters::style::RGBAColor;
+use strum_macros::{Display, EnumString};
+
+use crate::model::transaction::TransactionCategory;
+
+use super::{
+    plot_errors::PlotError,
+    plot_utils::{
+        palettes::{Palette, RED_PALETTE},
+        resolution::R720,
+        validate_date_format, NumberFormat, DEFAULT_DATE_FORMAT,
+    },
+};
+
+/// Image format of the saved figures
+#[derive(EnumString, Display, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    #[strum(serialize = "png", ascii_case_insensitive)]
+    Png,
+    #[strum(serialize = "jpg", serialize = "jpeg", ascii_case_insensitive)]
+    Jpeg,
+    #[strum(serialize = "bmp", ascii_case_insensitive)]
+    Bmp,
+}
+
+impl OutputFormat {
+    /// Returns the extension of the figure files, it selects the encoder of the image
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Bmp => "bmp",
+        }
+    }
+
+    /// Returns true if the format has an alpha channel for transparent backgrounds
+    pub fn supports_transparency(&self) -> bool {
+        *self != OutputFormat::Jpeg
+    }
+}
+
+/// Options of the plots
+///
+/// The defaults are 720p figures in png with the red palette, written in the
+/// current folder and drawn from the transactions of the Ale and Giulia accounts.
+///
+/// ```
+/// use realearning::plots::{
+///     plot_config::{OutputFormat, PlotConfig},
+///     plot_utils::{palettes::CB_FRIENDLY_PALETTE, resolution::R1080},
+/// };
+///
+/// let config = PlotConfig::new("plots")
+///     .resolution(R1080)
+///     .palette(CB_FRIENDLY_PALETTE.clone())
+///     .output_format(OutputFormat::Jpeg);
+/// assert_eq!(config.figure_file_name("transaction_pie"), "transaction_pie.jpg");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlotConfig {
+    pub(crate) folder: String,
+    pub(crate) prefix: Option<String>,
+    pub(crate) title_prefix: String,
+    pub(crate) resolution: (u32, u32),
+    pub(crate) palette: Palette,
+    pub(crate) output_format: OutputFormat,
+    pub(crate) transparent_background: bool,
+    pub(crate) number_format: NumberFormat,
+    pub(crate) date_format: String,
+    pub(crate) accounts: Option<Vec<String>>,
+    pub(crate) date_range: Option<(NaiveDate, NaiveDate)>,
+    pub(crate) category_colors: HashMap<TransactionCategory, RGBAColor>,
+}
+
+impl Default for PlotConfig {
+    fn default() -> Self {
+        PlotConfig::new(".")
+    }
+}
+
+impl PlotConfig {
+    /// Create the default config of the plots saved in `folder`
+    pub fn new(folder: &str) -> PlotConfig {
+        PlotConfig {
+            folder: String::from(folder),
+            prefix: None,
+            title_prefix: String::new(),
+            resolution: R720,
+            palette: RED_PALETTE.clone(),
+            output_format: OutputFormat::default(),
+            transparent_background: false,
+            number_format: NumberFormat::default(),
+            date_format: String::from(DEFAULT_DATE_FORMAT),
+            accounts: Some(vec![String::from("Ale"), String::from("Giulia")]),
+            date_range: None,
+            category_colors: HashMap::new(),
+        }
+    }
+
+    /// Prefix of the figure file names, to keep the figures of different runs in the same folder
+    pub fn prefix(mut self, prefix: Option<&str>) -> Self {
+        self.prefix = prefix.map(String::from);
+        self
+    }
+
+    /// Text prepended to the title of each plot
+    pub fn title_prefix(mut self, title_prefix: &str) -> Self {
+        self.title_prefix = String::from(title_prefix);
+        self
+    }
+
+    /// Width and height in pixels of the figures
+    pub fn resolution(mut self, resolution: (u32, u32)) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Colors of the plots and of their background
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Image format of the saved figures
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
+    /// Draw the figures on a transparent background instead of the palette one,
+    /// the output format must support transparency
+    pub fn transparent_background(mut self, transparent_background: bool) -> Self {
+        self.transparent_background = transparent_background;
+        self
+    }
+
+    /// Separators of the numbers in the labels
+    pub fn number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    /// Format of the dates in the labels, e.g. "%b %y" for May 23
+    pub fn date_format(mut self, date_format: &str) -> Self {
+        self.date_format = String::from(date_format);
+        self
+    }
+
+    /// Accounts whose transactions are plotted, `None` for all of them
+    pub fn accounts(mut self, accounts: Option<Vec<String>>) -> Self {
+        self.accounts = accounts;
+        self
+    }
+
+    /// Dates of the plotted transactions, both ends included
+    pub fn date_range(mut self, date_range: Option<(NaiveDate, NaiveDate)>) -> Self {
+        self.date_range = date_range;
+        self
+    }
+
+    /// Fixed colors of the categories, so that a category has the same color in
+    /// every plot and run, unmapped categories take the colors of the palette
+    pub fn category_colors(
+        mut self,
+        category_colors: HashMap<TransactionCategory, RGBAColor>,
+    ) -> Self {
+        self.category_colors = category_colors;
+        self
+    }
+
+    /// Returns the color of a category, the mapped one or the palette color at `index`
+    ///
+    /// The category is the serialized name of the dataframes, e.g. "PranzoLavoro".
+    pub fn category_color(&self, category: &str, index: usize) -> RGBAColor {
+        TransactionCategory::from_serialized_name(category)
+            .and_then(|category| self.category_colors.get(&category))
+            .copied()
+            .unwrap_or_else(|| self.palette.color(index))
+    }
+
+    /// Returns the background of the figures, `None` when it is transparent
+    pub fn background(&self) -> Option<RGBAColor> {
+        (!self.transparent_background).then_some(self.palette.background)
+    }
+
+    /// Returns the file name of a figure with the extension of the output format
+    pub fn figure_file_name(&self, name: &str) -> String {
+        format!("{name}.{}", self.output_format.extension())
+    }
+
+    /// Returns the format of the dates, an invalid one is a `PlotError::InvalidParameter`
+    pub(crate) fn checked_date_format(&self) -> Result<&str, PlotError> {
+        validate_date_format(&self.date_format).map_err(PlotError::InvalidParameter)?;
+        Ok(&self.date_format)
+    }
+
+    /// Returns the account filter in the form taken by the extractions
+    pub(crate) fn account_filter(&self) -> Option<&Vec<String>> {
+        self.accounts.as_ref()
+    }
+
+    /// Returns the date filter in the form taken by the extractions
+    pub(crate) fn date_filter(&self) -> Option<(&NaiveDate, &NaiveDate)> {
+        self.date_range.as_ref().map(|(from, to)| (from, to))
+    }
+}
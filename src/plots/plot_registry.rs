@@ -1,29 +1,127 @@
+use crate::model::account::TransactionAccountName;
 use crate::model::registry::Registry;
+use crate::model::transaction::TransactionCategory;
 use crate::plots::extraction::monthy_extraction;
+use crate::plots::plot_errors::PlotError;
+use chrono::NaiveDate;
 use indicatif::{MultiProgress, ProgressBar, ProgressIterator, ProgressStyle};
+use plotters::coord::Shift;
 use plotters::prelude::*;
 use std::cmp::Ordering::Equal;
-use super::extraction::{extract_categories_split, extract_daily_transactions};
-use super::plot_utils::palettes::Palette;
+use std::collections::HashMap;
+use std::fs;
+use super::extraction::{
+    extract_accounts_cumulative, extract_budget_comparison, extract_categories_split,
+    extract_daily_transactions, extract_dow_month_matrix, extract_monthly_income_expense,
+    extract_weekday_spending, savings_rate, OTHER_CATEGORY,
+};
+use super::figure_backend::FigureBackend;
+use super::plot_config::PlotConfig;
+use super::plot_utils::{interpolate_color, nice_step};
 
+/// Returns the label of the date at index `x` formatted with `date_format`
+///
+/// Indexes outside of `dates` get an empty label.
+fn date_label(dates: &[NaiveDate], x: f64, date_format: &str) -> String {
+    dates
+        .get(x as usize)
+        .map(|date| date.format(date_format).to_string())
+        .unwrap_or_default()
+}
+
+/// Returns the title of a plot preceded by `title_prefix`, if not empty
+fn build_title(title_prefix: &str, title: &str) -> String {
+    if title_prefix.is_empty() {
+        String::from(title)
+    } else {
+        format!("{title_prefix} {title}")
+    }
+}
+
+/// Returns the path of a figure inside the folder
+///
+/// When a prefix is given it is joined to the file name with an underscore,
+/// so that figures of different runs can be stored in the same folder.
+fn build_figure_path(folder: &str, prefix: Option<&str>, file_name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{folder}/{prefix}_{file_name}"),
+        None => format!("{folder}/{file_name}"),
+    }
+}
+
+/// Returns the path of a figure in the folder of the config, or in its `subfolder`,
+/// with the prefix and the output format of the config
+fn config_figure_path(config: &PlotConfig, subfolder: Option<&str>, name: &str) -> String {
+    let folder = match subfolder {
+        Some(subfolder) => format!("{}/{subfolder}", config.folder),
+        None => config.folder.clone(),
+    };
+    build_figure_path(
+        &folder,
+        config.prefix.as_deref(),
+        &config.figure_file_name(name),
+    )
+}
+
+/// Fills the area with the background of the config, if not transparent
+///
+/// The figures start transparent, see `FigureBackend`. A transparent background
+/// in a format without alpha channel is a `PlotError::InvalidParameter`.
+fn fill_background(
+    area: &DrawingArea<FigureBackend, Shift>,
+    config: &PlotConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match config.background() {
+        Some(color) => area.fill(&color)?,
+        None if config.output_format.supports_transparency() => {}
+        None => {
+            return Err(Box::new(PlotError::InvalidParameter(format!(
+                "the {} format has no transparent background",
+                config.output_format
+            ))))
+        }
+    }
+    Ok(())
+}
+
+/// Plots the daily net amount and its cumulative sum
+///
+/// When `mark_extremes` is true the all-time low and high of the cumulative sum
+/// are marked with their date and amount.
+/// The optional `goal` is a pair of cumulative amounts at the first and last day,
+/// a straight line between them is drawn on the cumulative chart and the gap
+/// between the cumulative sum and the goal is annotated at the last day.
 pub fn plot_daily_transactions(
     registry: &Registry,
-    resolution: (u32, u32),
-    folder: &str,
-    palette: &Palette,
+    config: &PlotConfig,
+    mark_extremes: bool,
+    goal: Option<(f32, f32)>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let figure_path = format!("{folder}/daily_transactions.png");
+    let resolution = config.resolution;
+    let palette = &config.palette;
+    let number_format = &config.number_format;
+    let date_format = config.checked_date_format()?;
+    let title_prefix = config.title_prefix.as_str();
+    let figure_path = config_figure_path(config, None, "daily_transactions");
 
-    let account_vec = vec![String::from("Ale"), String::from("Giulia")];
-    let daily_transactions =
-        extract_daily_transactions(registry, Some(&account_vec), None, true).unwrap();
+    let daily_transactions = extract_daily_transactions(
+        registry,
+        config.account_filter(),
+        config.date_filter(),
+        None,
+        None,
+        true,
+        None,
+    )?;
 
-    let colors = palette.colors;
 
     // Create the root drawing area
-    let root = BitMapBackend::new(&figure_path, resolution).into_drawing_area();
-    root.fill(&palette.background)?;
-    let root = root.titled("Daily transactions", ("sans-serif", 30))?;
+    let root = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root, config)?;
+    let root = root.titled(
+        &build_title(title_prefix, "Daily transactions"),
+        ("sans-serif", 30),
+    )?;
     let (upper, lower) = root.split_vertically(resolution.1 / 2);
 
     //let root = root.margin(10, 10, 10, 10);
@@ -36,7 +134,8 @@ pub fn plot_daily_transactions(
         .caption("timeseries", ("sans-serif", 20))
         .build_cartesian_2d(
             (daily_transactions.days_idx_range.0..(daily_transactions.days_idx_range.1)).step(1.0),
-            (daily_transactions.amounts_range.0..(daily_transactions.amounts_range.1)).step(500.0),
+            (daily_transactions.amounts_range.0..(daily_transactions.amounts_range.1))
+                .step(nice_step(daily_transactions.amounts_range)),
         )?;
 
     upper_chart
@@ -48,33 +147,51 @@ pub fn plot_daily_transactions(
         })
         .x_labels(30) // number of labels per axis
         .y_labels(20)
-        .y_label_formatter(&|x| format!("{:.0}", x))
-        .x_label_formatter(&|x| format!("{:.3}", daily_transactions.days.get(*x as usize).unwrap()))
+        .y_label_formatter(&|x| number_format.format(*x, 0))
+        .x_label_formatter(&|x| date_label(&daily_transactions.days, *x, date_format))
         .y_desc("Euros")
         .x_desc("Days")
         .draw()?;
-    upper_chart.draw_series(
-        LineSeries::new(
-            daily_transactions.days_idx.iter().map(|&x| (x, 0.0)).collect::<Vec<(f32, f32)>>(),
-        ShapeStyle {
-            color: RGBAColor(0, 0, 0, 1.0),
-            filled: false,
-            stroke_width: 1,
-        }
-    )
-    ).unwrap();
-    upper_chart.draw_series(
-        LineSeries::new(
-            daily_transactions.amounts_pairs,
-            ShapeStyle {
-                color: colors[0],
-                filled: true,
-                stroke_width: 2,
-            },
-        )
-        .point_size(2),
-    )?;
-    
+    let zero_style = ShapeStyle {
+        color: RGBAColor(0, 0, 0, 1.0),
+        filled: false,
+        stroke_width: 1,
+    };
+    upper_chart
+        .draw_series(LineSeries::new(
+            daily_transactions
+                .days_idx
+                .iter()
+                .map(|&x| (x, 0.0))
+                .collect::<Vec<(f64, f64)>>(),
+            zero_style,
+        ))?
+        .label("Zero")
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], zero_style));
+    let amounts_style = ShapeStyle {
+        color: palette.color(0),
+        filled: true,
+        stroke_width: 2,
+    };
+    upper_chart
+        .draw_series(
+            LineSeries::new(daily_transactions.amounts_pairs, amounts_style).point_size(2),
+        )?
+        .label("Daily net")
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], amounts_style));
+    upper_chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    // The goal line is kept inside the cumulative chart
+    let goal = goal.map(|(start, end)| (f64::from(start), f64::from(end)));
+    let (low, high) = daily_transactions.cumsum_amounts_range;
+    let cumsum_range = match goal {
+        Some((start, end)) => (low.min(start).min(end), high.max(start).max(end)),
+        None => (low, high),
+    };
 
     let mut cumulative_chart = ChartBuilder::on(&lower)
         .caption("cumulative transactions", ("sans-serif", 20).into_font())
@@ -85,22 +202,9 @@ pub fn plot_daily_transactions(
         .margin_bottom(20)
         .build_cartesian_2d(
             (daily_transactions.days_idx_range.0..(daily_transactions.days_idx_range.1)).step(1.0),
-            (daily_transactions.cumsum_amounts_range.0
-                ..(daily_transactions.cumsum_amounts_range.1))
-                .step(1000.0),
+            (cumsum_range.0..cumsum_range.1).step(nice_step(cumsum_range)),
         )?;
 
-    cumulative_chart.draw_series(
-        LineSeries::new(
-            daily_transactions.amount_cumulative_pairs,
-            ShapeStyle {
-                color: colors[0],
-                filled: true,
-                stroke_width: 2,
-            },
-        )
-        .point_size(2),
-    )?;
     cumulative_chart
         .configure_mesh()
         .bold_line_style(ShapeStyle {
@@ -110,105 +214,474 @@ pub fn plot_daily_transactions(
         })
         .x_labels(30) // number of labels per axis
         .y_labels(20)
-        .y_label_formatter(&|x| format!("{:.0}", x))
-        .x_label_formatter(&|x| format!("{:.3}", daily_transactions.days.get(*x as usize).unwrap()))
+        .y_label_formatter(&|x| number_format.format(*x, 0))
+        .x_label_formatter(&|x| date_label(&daily_transactions.days, *x, date_format))
         .y_desc("Euros")
         .x_desc("Days")
         .draw()?;
+    cumulative_chart
+        .draw_series(
+            LineSeries::new(daily_transactions.amount_cumulative_pairs, amounts_style)
+                .point_size(2),
+        )?
+        .label("Cumulative net")
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], amounts_style));
+    if mark_extremes {
+        let (low, high) = daily_transactions.cumsum_amounts_range;
+        // The labels are moved inside the chart, below the high and above the low
+        let extremes = [(low, -20), (high, 5)]
+            .into_iter()
+            .filter_map(|(value, offset)| {
+                daily_transactions
+                    .cumsum_amounts
+                    .iter()
+                    .position(|x| *x == value)
+                    .map(|i| (daily_transactions.days_idx[i], value, offset))
+            });
+        cumulative_chart.draw_series(extremes.map(|(x, y, offset)| {
+            let label = format!(
+                "{} {}",
+                date_label(&daily_transactions.days, x, date_format),
+                number_format.format(y, 2)
+            );
+            EmptyElement::at((x, y))
+                + Circle::new((0, 0), 4, palette.color(0).filled())
+                + Text::new(label, (5, offset), ("sans-serif", 15).into_font())
+        }))?;
+    }
+    if let Some((goal_start, goal_end)) = goal {
+        let first_day = daily_transactions.days_idx_range.0;
+        let last_day = daily_transactions.days_idx_range.1;
+        let goal_style = ShapeStyle {
+            color: palette.color(1),
+            filled: false,
+            stroke_width: 2,
+        };
+        cumulative_chart
+            .draw_series(LineSeries::new(
+                [(first_day, goal_start), (last_day, goal_end)],
+                goal_style,
+            ))?
+            .label("Goal")
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], goal_style));
+        if let Some(last_cumsum) = daily_transactions.cumsum_amounts.last() {
+            let label = format!("gap {}", number_format.format(last_cumsum - goal_end, 2));
+            cumulative_chart.draw_series(std::iter::once(
+                EmptyElement::at((last_day, goal_end))
+                    + Circle::new((0, 0), 4, palette.color(1).filled())
+                    + Text::new(label, (-90, 5), ("sans-serif", 15).into_font()),
+            ))?;
+        }
+    }
+    cumulative_chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
 
     root.present()?;
 
     Ok(())
 }
 
+/// Plots the cumulative net amount of the accounts as stacked bands
+///
+/// Each band spans from the sum of the previous accounts to the sum including
+/// its account, so the top of the stack is the overall cumulative net.
+pub fn plot_account_cumulative(
+    registry: &Registry,
+    config: &PlotConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let resolution = config.resolution;
+    let palette = &config.palette;
+    let number_format = &config.number_format;
+    let date_format = config.checked_date_format()?;
+    let title_prefix = config.title_prefix.as_str();
+    let figure_path = config_figure_path(config, None, "account_cumulative");
+
+    let accounts_cumulative = extract_accounts_cumulative(
+        registry,
+        config.account_filter(),
+        config.date_filter(),
+        None,
+        true,
+    )?;
+
+    let n_days = accounts_cumulative.days.len();
+    let mut layers: Vec<Vec<f64>> = vec![vec![0.0; n_days]];
+    for cumsum in accounts_cumulative.cumsum_amounts.iter() {
+        let previous = layers.last().unwrap();
+        layers.push(previous.iter().zip(cumsum).map(|(x, y)| x + y).collect());
+    }
+    let y_range = layers.iter().flatten().fold((0.0f64, 0.0f64), |range, x| {
+        (range.0.min(*x), range.1.max(*x))
+    });
+
+    let root = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root, config)?;
+    let root = root.titled(
+        &build_title(title_prefix, "Accounts cumulative transactions"),
+        ("sans-serif", 30),
+    )?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .margin_left(30)
+        .margin_right(30)
+        .margin_bottom(20)
+        .caption("cumulative transactions per account", ("sans-serif", 20))
+        .build_cartesian_2d(
+            (0.0..(n_days.max(2) - 1) as f64).step(1.0),
+            (y_range.0..y_range.1).step(nice_step(y_range)),
+        )?;
+
+    chart
+        .configure_mesh()
+        .bold_line_style(ShapeStyle {
+            color: palette.mesh,
+            filled: false,
+            stroke_width: 1,
+        })
+        .x_labels(30)
+        .y_labels(20)
+        .y_label_formatter(&|x| number_format.format(*x, 0))
+        .x_label_formatter(&|x| date_label(&accounts_cumulative.days, *x, date_format))
+        .y_desc("Euros")
+        .x_desc("Days")
+        .draw()?;
+
+    for (i, account) in accounts_cumulative.accounts.iter().enumerate() {
+        let color = palette.color(i);
+        let upper = layers[i + 1]
+            .iter()
+            .enumerate()
+            .map(|(x, y)| (x as f64, *y));
+        let lower = layers[i]
+            .iter()
+            .enumerate()
+            .map(|(x, y)| (x as f64, *y))
+            .rev();
+        chart
+            .draw_series(std::iter::once(Polygon::new(
+                upper.chain(lower).collect::<Vec<(f64, f64)>>(),
+                color.filled(),
+            )))?
+            .label(TransactionAccountName::display_name_of(account))
+            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.filled()));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    root.present()?;
+    Ok(())
+}
+
+/// Pies drawn by `plot_category_pie`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PieMode {
+    /// Expenses and entries pies side by side
+    Both,
+    /// Only the expenses pie, drawn on the full area
+    ExpensesOnly,
+    /// Only the entries pie, drawn on the full area
+    IncomeOnly,
+}
+
+/// Order of the slices drawn by `plot_category_pie`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SliceOrder {
+    /// Largest slices first, as sorted by `extract_categories_split`
+    #[default]
+    BySize,
+    /// Alphabetical order of the category names, "Other" is the last slice,
+    /// so that the same category has the same position in every pie
+    ByName,
+}
+
+/// Values shown on the pie slices
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PieLabel {
+    /// Percentage of the slice over the whole pie
+    Percentage,
+    /// Absolute amount in euros of the slice
+    Absolute,
+    /// Both percentage and absolute amount
+    Both,
+}
+
+/// Style of the pies with sizes relative to the drawing area of each pie
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PieStyle {
+    /// Angle in degrees where the first slice starts
+    pub start_angle: f64,
+    /// Radius as a fraction of the smallest dimension of the drawing area
+    pub radius_fraction: f64,
+    /// Font size of the slice labels as a fraction of the radius
+    pub label_font_fraction: f64,
+    /// Font size of the percentages as a fraction of the radius
+    pub percentage_font_fraction: f64,
+}
+
+impl Default for PieStyle {
+    fn default() -> Self {
+        PieStyle {
+            start_angle: 66.0,
+            radius_fraction: 0.3,
+            label_font_fraction: 0.08,
+            percentage_font_fraction: 0.08,
+        }
+    }
+}
+
+impl PieStyle {
+    /// Returns the radius of a pie in a drawing area with dimensions `dims`
+    pub fn radius(&self, dims: (u32, u32)) -> f64 {
+        f64::from(dims.0.min(dims.1)) * self.radius_fraction
+    }
+}
+
+/// Plots the pies of the expense and income categories
 pub fn plot_category_pie(
     registry: &Registry,
-    resolution: (u32, u32),
+    config: &PlotConfig,
     max_categories: usize,
-    folder: &str,
-    palette: &Palette,
+    mode: PieMode,
+    order: SliceOrder,
+    label: PieLabel,
+    style: &PieStyle,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let account_vec = vec![String::from("Ale"), String::from("Giulia")];
-    let categories_split =
-        extract_categories_split(registry, Some(&account_vec), None, Some(max_categories)).unwrap();
+    let resolution = config.resolution;
+    let title_prefix = config.title_prefix.as_str();
+    let categories_split = extract_categories_split(
+        registry,
+        config.account_filter(),
+        config.date_filter(),
+        None,
+        Some(max_categories),
+    )?;
 
-    let figure_path = format!("{folder}/transaction_pie.png");
+    let figure_path = config_figure_path(config, None, "transaction_pie");
 
-    let root_area = BitMapBackend::new(&figure_path, resolution).into_drawing_area();
-    root_area.fill(&WHITE).unwrap();
+    let root_area = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root_area, config)?;
     let title_style = TextStyle::from(("sans-serif", 30).into_font()).color(&(BLACK));
     root_area
-        .titled("Categories Pie Chart", title_style)
+        .titled(
+            &build_title(title_prefix, "Categories Pie Chart"),
+            title_style,
+        )
         .unwrap();
-    let (left, right) = root_area.split_horizontally(resolution.0 / 2);
-    left.titled("Expenses", ("sans-serif", 20).into_font())?;
-    right.titled("Entries", ("sans-serif", 20).into_font())?;
-
-    // Expenses
-    let dims = left.dim_in_pixel();
-    let center = (dims.0 as i32 / 2, dims.1 as i32 / 2);
-    let radius = 250.0;
-    let colors: Vec<RGBColor> = (0..categories_split.expense_categories.len())
-        .map(|x| {
-            let (r, g, b) = palette.colors[x].rgb();
-            RGBColor(r, g, b)
-        })
-        .collect();
 
-    let mut pie = Pie::new(
-        &center,
-        &radius,
+    let expenses = (
+        "Expenses",
         &categories_split.expense_percentages,
-        &colors,
+        &categories_split.expense_amounts,
         &categories_split.expense_categories,
     );
-    pie.start_angle(66.0);
-    pie.label_style((("sans-serif", 20).into_font()).color(&(BLACK)));
-    pie.percentages((("sans-serif", radius * 0.08).into_font()).color(&BLACK));
-    left.draw(&pie)?;
-
-    // Entries
-    let dims = right.dim_in_pixel();
-    let center = (
-        dims.0 as i32 / 2 + resolution.0 as i32 / 2,
-        dims.1 as i32 / 2,
+    let entries = (
+        "Entries",
+        &categories_split.income_percentages,
+        &categories_split.income_amounts,
+        &categories_split.income_categories,
     );
-    let colors: Vec<RGBColor> = (0..categories_split.income_categories.len())
-        .map(|x| {
-            let (r, g, b) = palette.colors[x].rgb();
-            RGBColor(r, g, b)
+    let pies = match mode {
+        PieMode::Both => {
+            let (left, right) = root_area.split_horizontally(resolution.0 / 2);
+            vec![(left, expenses), (right, entries)]
+        }
+        PieMode::ExpensesOnly => vec![(root_area.clone(), expenses)],
+        PieMode::IncomeOnly => vec![(root_area.clone(), entries)],
+    };
+
+    for (area, (title, percentages, amounts, categories)) in pies {
+        area.titled(title, ("sans-serif", 20).into_font())?;
+        let slices = slice_order(categories, order);
+        let pick = |values: &[f64]| -> Vec<f64> { slices.iter().map(|i| values[*i]).collect() };
+        let categories: Vec<String> = slices.iter().map(|i| categories[*i].clone()).collect();
+        draw_categories_pie(
+            &area,
+            &pick(percentages),
+            &pick(amounts),
+            &categories,
+            config,
+            label,
+            style,
+        )?;
+    }
+    root_area.present()?;
+    Ok(())
+}
+
+/// Returns the indexes of the pie slices in the drawing order
+fn slice_order(categories: &[String], order: SliceOrder) -> Vec<usize> {
+    let mut slices: Vec<usize> = (0..categories.len()).collect();
+    if order == SliceOrder::ByName {
+        slices.sort_by_key(|i| {
+            (
+                categories[*i] == OTHER_CATEGORY,
+                categories[*i].to_lowercase(),
+            )
+        });
+    }
+    slices
+}
+
+/// Returns the colors of the slices of a pie, one for each of its categories
+///
+/// Categories without a fixed color in the config prefer the palette color at
+/// their position in `TransactionCategory`, not the position of their slice,
+/// then a category keeps its color across the pies. When that color is already
+/// taken in the pie the next free one of the palette is used, so the slices have
+/// distinct colors as long as the palette has enough of them.
+/// "Other" and unknown names prefer the color after the last category.
+fn pie_colors(categories: &[String], config: &PlotConfig) -> Vec<RGBColor> {
+    let preferred: Vec<(Option<RGBAColor>, usize)> = categories
+        .iter()
+        .map(|name| {
+            let category = TransactionCategory::from_serialized_name(name);
+            let fixed = category
+                .as_ref()
+                .and_then(|category| config.category_colors.get(category))
+                .copied();
+            let index = category.map_or(
+                TransactionCategory::Uncategorized as usize + 1,
+                |category| category as usize,
+            );
+            (fixed, index)
         })
         .collect();
 
-    let mut pie = Pie::new(
-        &center,
-        &radius,
-        &categories_split.income_percentages,
-        &colors,
-        &categories_split.income_categories,
+    let mut colors: Vec<Option<RGBAColor>> = preferred.iter().map(|(fixed, _)| *fixed).collect();
+    let n_colors = config.palette.colors.len();
+    // categories earlier in `TransactionCategory` pick first, whatever their slice
+    let mut unmapped: Vec<usize> = (0..categories.len())
+        .filter(|i| colors[*i].is_none())
+        .collect();
+    unmapped.sort_by_key(|i| preferred[*i].1);
+    for i in unmapped {
+        let index = preferred[i].1;
+        let color = (index..index + n_colors)
+            .map(|index| config.palette.color(index))
+            .find(|color| !colors.contains(&Some(*color)))
+            .unwrap_or_else(|| config.palette.color(index));
+        colors[i] = Some(color);
+    }
+
+    colors
+        .into_iter()
+        .flatten()
+        .map(|color| {
+            let (r, g, b) = color.rgb();
+            RGBColor(r, g, b)
+        })
+        .collect()
+}
+
+/// Builds the slice labels of a pie
+///
+/// The category name is followed by the absolute amount when `label`
+/// requires it, percentages are drawn by the pie itself.
+fn pie_labels(categories: &[String], amounts: &[f64], label: PieLabel) -> Vec<String> {
+    match label {
+        PieLabel::Percentage => categories.to_vec(),
+        PieLabel::Absolute | PieLabel::Both => categories
+            .iter()
+            .zip(amounts)
+            .map(|(category, amount)| format!("{category} {:.0}€", amount.abs()))
+            .collect(),
+    }
+}
+
+/// Draws a pie centered in the drawing area with one slice per category
+///
+/// Slices are colored by their category, see `pie_colors`.
+/// Nothing is drawn when there are no categories, e.g. a side without transactions.
+fn draw_categories_pie(
+    area: &DrawingArea<FigureBackend, Shift>,
+    percentages: &[f64],
+    amounts: &[f64],
+    categories: &[String],
+    config: &PlotConfig,
+    label: PieLabel,
+    style: &PieStyle,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if categories.is_empty() {
+        return Ok(());
+    }
+    let dims = area.dim_in_pixel();
+    let radius = style.radius(dims);
+    let base_pixel = area.get_base_pixel();
+    let center = (
+        base_pixel.0 + dims.0 as i32 / 2,
+        base_pixel.1 + dims.1 as i32 / 2,
     );
-    pie.start_angle(66.0);
-    pie.label_style((("sans-serif", 20).into_font()).color(&(BLACK)));
-    pie.percentages((("sans-serif", radius * 0.08).into_font()).color(&BLACK));
-    right.draw(&pie)?;
+    let colors = pie_colors(categories, config);
+
+    let labels = pie_labels(categories, amounts, label);
+
+    let mut pie = Pie::new(&center, &radius, percentages, &colors, &labels);
+    pie.start_angle(style.start_angle);
+    pie.label_style(
+        (("sans-serif", radius * style.label_font_fraction).into_font()).color(&(BLACK)),
+    );
+    if label != PieLabel::Absolute {
+        pie.percentages(
+            (("sans-serif", radius * style.percentage_font_fraction).into_font()).color(&BLACK),
+        );
+    }
+    area.draw(&pie)?;
     Ok(())
 }
 
+/// Plots the monthly net income and the monthly expenses of each category
+///
+/// When `income_percentage_labels` is true each month of the net income is labeled
+/// with its savings rate, months without income are not labeled.
+/// The categories follow `category_order`, see `monthy_extraction`.
+/// The monthly pies are drawn in a grid with `pie_grid_cols` columns, zero columns
+/// is a `PlotError::InvalidParameter`.
+#[allow(clippy::too_many_arguments)]
 pub fn plot_monthly_report(
     registry: &Registry,
-    resolution: (u32, u32),
+    config: &PlotConfig,
     max_categories: Option<usize>,
-    folder: &str,
-    palette: &Palette,
+    label: PieLabel,
+    style: &PieStyle,
+    pie_grid_cols: usize,
+    income_percentage_labels: bool,
+    category_order: Option<&[TransactionCategory]>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let account_vec = vec![String::from("Ale"), String::from("Giulia")];
-    let monthly_extraction = monthy_extraction(registry, Some(&account_vec), None, max_categories)?;
+    let resolution = config.resolution;
+    let palette = &config.palette;
+    let number_format = &config.number_format;
+    let date_format = config.checked_date_format()?;
+    let title_prefix = config.title_prefix.as_str();
+    if pie_grid_cols == 0 {
+        return Err(Box::new(PlotError::InvalidParameter(String::from(
+            "the pie grid needs at least one column",
+        ))));
+    }
+    let monthly_extraction = monthy_extraction(
+        registry,
+        config.account_filter(),
+        config.date_filter(),
+        None,
+        None,
+        max_categories,
+        category_order,
+    )?;
 
-    let figure_path = format!("{folder}/monthly_net_ts.png");
-    let colors = palette.colors;
-    let root_area = BitMapBackend::new(&figure_path, resolution).into_drawing_area();
-    root_area.fill(&WHITE).unwrap();
-    root_area.titled("Monthly Plots", ("sans-serif", 30))?;
+    let figure_path = config_figure_path(config, None, "monthly_net_ts");
+    let root_area = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root_area, config)?;
+    root_area.titled(
+        &build_title(title_prefix, "Monthly Plots"),
+        ("sans-serif", 30),
+    )?;
 
     // UPPER
     let mut upper_chart = ChartBuilder::on(&root_area)
@@ -222,33 +695,48 @@ pub fn plot_monthly_report(
             (monthly_extraction.months_idx_range.0..(monthly_extraction.months_idx_range.1))
                 .step(1.0),
             (monthly_extraction.net_income_range.0..(monthly_extraction.net_income_range.1))
-                .step(100.0),
+                .step(nice_step(monthly_extraction.net_income_range)),
         )?;
 
     upper_chart
         .configure_mesh()
         .x_labels(monthly_extraction.months_idx.len()) // number of labels per axis
         .y_labels(20)
-        .y_label_formatter(&|x| format!("{:.0}", x))
-        .x_label_formatter(&|x| format!("{}", monthly_extraction.months.get(*x as usize).unwrap()))
+        .y_label_formatter(&|x| number_format.format(*x, 0))
+        .x_label_formatter(&|x| date_label(&monthly_extraction.months, *x, date_format))
         .y_desc("Euros")
         .x_desc("Months")
         .draw()?;
     upper_chart.draw_series(
         LineSeries::new(
-            monthly_extraction.net_income_pairs,
+            monthly_extraction.net_income_pairs.clone(),
             ShapeStyle {
-                color: colors[0],
+                color: palette.color(0),
                 filled: true,
                 stroke_width: 2,
             },
         )
         .point_size(3),
     )?;
+    if income_percentage_labels {
+        let rates = monthly_extraction
+            .net_income_pairs
+            .iter()
+            .zip(monthly_extraction.gross_income.iter())
+            .filter_map(|(&(x, y), gross)| savings_rate(y, *gross).map(|rate| (x, y, rate)));
+        upper_chart.draw_series(rates.map(|(x, y, rate)| {
+            EmptyElement::at((x, y))
+                + Text::new(
+                    format!("{}%", number_format.format(rate, 1)),
+                    (-10, -20),
+                    ("sans-serif", 15).into_font(),
+                )
+        }))?;
+    }
 
     upper_chart.draw_series(
         LineSeries::new(
-            monthly_extraction.months_idx.iter().map(|&x| (x, 0.0)).collect::<Vec<(f32, f32)>>(),
+            monthly_extraction.months_idx.iter().map(|&x| (x, 0.0)).collect::<Vec<(f64, f64)>>(),
         ShapeStyle {
             color: RGBAColor(0, 0, 0, 1.0),
             filled: true,
@@ -259,6 +747,8 @@ pub fn plot_monthly_report(
     root_area.present()?;
 
     // MID
+    // The plots of the categories are stored in their own subfolder
+    fs::create_dir_all(format!("{}/categories", config.folder))?;
     let multi_progress = MultiProgress::new();
     let progress_bar = multi_progress.add(ProgressBar::new(monthly_extraction.categories.len() as u64));
     for (i, category) in monthly_extraction.categories.iter().enumerate().progress_with(progress_bar) {
@@ -287,10 +777,14 @@ pub fn plot_monthly_report(
         let pairs = monthly_extraction.categories_pairs.get(i).unwrap().clone();
         let min_y = pairs.iter().map(|x| x.1).min_by(|x, y| x.partial_cmp(y).unwrap_or(Equal)).unwrap();
         let max_y = pairs.iter().map(|x| x.1).max_by(|x, y| x.partial_cmp(y).unwrap_or(Equal)).unwrap();
-        let categories_figure_path = format!("{folder}/categories/monthly_{category}.png");
-        let root_area = BitMapBackend::new(&categories_figure_path, resolution).into_drawing_area();
-        root_area.fill(&WHITE).unwrap();
-        root_area.titled(&format!("Monthly Plot {category}"), ("sans-serif", 30))?;
+        let categories_figure_path =
+            config_figure_path(config, Some("categories"), &format!("monthly_{category}"));
+        let root_area = FigureBackend::new(&categories_figure_path, resolution).into_drawing_area();
+        fill_background(&root_area, config)?;
+        root_area.titled(
+            &build_title(title_prefix, &format!("Monthly Plot {category}")),
+            ("sans-serif", 30),
+        )?;
         let mut mid_chart = ChartBuilder::on(&root_area)
             .x_label_area_size(50)
             .y_label_area_size(50)
@@ -310,21 +804,20 @@ pub fn plot_monthly_report(
             .x_labels(12) // number of labels per axis
             .y_labels(30)
             //.y_label_formatter(&|x| format!("{:.0}", 10.0.pow(x))) logarithmic
-            .y_label_formatter(&|x| format!("{:.0}", x))
-            .x_label_formatter(&|x| {
-                format!("{:.3}", monthly_extraction.months.get(*x as usize).unwrap())
-            })
+            .y_label_formatter(&|x| number_format.format(*x, 0))
+            .x_label_formatter(&|x| date_label(&monthly_extraction.months, *x, date_format))
             .y_desc("Euros")
             .x_desc("Month")
             .draw()?;
 
 
+        let color = config.category_color(category, i);
         mid_chart
             .draw_series(
                 LineSeries::new(
                     pairs,
                     ShapeStyle {
-                        color: colors[i],
+                        color,
                         filled: true,
                         stroke_width: 2,
                     },
@@ -337,33 +830,26 @@ pub fn plot_monthly_report(
                 PathElement::new(
                     vec![(x, y), (x + 20, y)],
                     ShapeStyle {
-                        color: colors[i],
+                        color,
                         filled: true,
                         stroke_width: 2,
                     },
                 )
             });
-        root_area.present()?; 
+        root_area.present()?;
         spinner.finish_with_message(format!("{category} plot done"));
 
         
     }
     
-    let figure_path = format!("{folder}/monthly_category_pies.png");
+    let figure_path = config_figure_path(config, None, "monthly_category_pies");
 
-    let root_area = BitMapBackend::new(&figure_path, resolution).into_drawing_area();
-    root_area.fill(&WHITE).unwrap();
+    let root_area = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root_area, config)?;
     //root_area.titled("Monthly Pies", ("sans-serif", 30))?;
     let n_months = monthly_extraction.months.len();
-    let cols = 3;
-    let rows = (n_months as f32 / cols as f32).ceil() as usize;
-    let drawing_areas = root_area.split_evenly((rows, cols));
-    let colors: Vec<RGBColor> = (0..monthly_extraction.categories.len())
-        .map(|x| {
-            let (r, g, b) = palette.colors[x].rgb();
-            RGBColor(r, g, b)
-        })
-        .collect();
+    let rows = n_months.div_ceil(pie_grid_cols);
+    let drawing_areas = root_area.split_evenly((rows, pie_grid_cols));
 
     for (i, month) in monthly_extraction
         .categories_amounts_perc_months
@@ -373,53 +859,817 @@ pub fn plot_monthly_report(
         let da = drawing_areas.get(i).unwrap();
         da.titled(&month.to_string(), ("sans-serif", 20))?;
 
-        let dims = da.dim_in_pixel();
+        draw_categories_pie(
+            da,
+            monthly_extraction.categories_amounts_perc.get(i).unwrap(),
+            monthly_extraction
+                .categories_amounts_perc_value
+                .get(i)
+                .unwrap(),
+            monthly_extraction
+                .categories_amounts_perc_names
+                .get(i)
+                .unwrap(),
+            config,
+            label,
+            style,
+        )?;
+    }
 
-        let base_pixel = da.get_base_pixel();
+    root_area.present()?;
+    Ok(())
+}
 
-        let center = (
-            (base_pixel.0 + dims.0 as i32 / 2),
-            (base_pixel.1 + dims.1 as i32 / 2),
-        );
+/// Plots the pie of the expense categories of each month in its own figure
+///
+/// Figures are named after their month, e.g. `pie_2023-03.png`, so that single
+/// months can be embedded without the grid of `plot_monthly_report`.
+/// The categories follow `category_order`, see `monthy_extraction`.
+pub fn plot_monthly_pies_separate(
+    registry: &Registry,
+    config: &PlotConfig,
+    max_categories: Option<usize>,
+    label: PieLabel,
+    style: &PieStyle,
+    category_order: Option<&[TransactionCategory]>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let resolution = config.resolution;
+    let title_prefix = config.title_prefix.as_str();
+    let monthly_extraction = monthy_extraction(
+        registry,
+        config.account_filter(),
+        config.date_filter(),
+        None,
+        None,
+        max_categories,
+        category_order,
+    )?;
 
-        let radius: f64 = (dims.0 / 4) as f64;
-        let mut labels: Vec<String> = Vec::new();
-        for (j, category_name) in monthly_extraction
-            .categories_amounts_perc_names
-            .get(i)
-            .unwrap()
+    for (i, month) in monthly_extraction
+        .categories_amounts_perc_months
+        .iter()
+        .enumerate()
+    {
+        let month = NaiveDate::parse_from_str(month, "%Y-%m-%d")?;
+        let figure_path =
+            config_figure_path(config, None, &format!("pie_{}", month.format("%Y-%m")));
+        let root_area = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+        fill_background(&root_area, config)?;
+        root_area.titled(
+            &build_title(title_prefix, &month.format("%B %Y").to_string()),
+            ("sans-serif", 30),
+        )?;
+
+        draw_categories_pie(
+            &root_area,
+            &monthly_extraction.categories_amounts_perc[i],
+            &monthly_extraction.categories_amounts_perc_value[i],
+            &monthly_extraction.categories_amounts_perc_names[i],
+            config,
+            label,
+            style,
+        )?;
+        root_area.present()?;
+    }
+    Ok(())
+}
+
+/// Returns the layers of a stacked area chart of monthly expenses
+///
+/// Each layer sums the absolute amounts of its category and of the previous ones,
+/// months without transactions of a category count as zero.
+fn stack_category_pairs(
+    n_months: usize,
+    categories_pairs: &[&Vec<(f64, f64)>],
+) -> Vec<Vec<(f64, f64)>> {
+    let mut totals = vec![0.0; n_months];
+    categories_pairs
+        .iter()
+        .map(|pairs| {
+            for (month_idx, amount) in pairs.iter() {
+                totals[*month_idx as usize] += amount.abs();
+            }
+            totals
+                .iter()
+                .enumerate()
+                .map(|(i, total)| (i as f64, *total))
+                .collect()
+        })
+        .collect()
+}
+
+/// Plots the monthly expenses of each category as stacked areas
+///
+/// Categories with the highest expenses are at the bottom of the stack.
+pub fn plot_monthly_stacked_area(
+    registry: &Registry,
+    config: &PlotConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let resolution = config.resolution;
+    let palette = &config.palette;
+    let number_format = &config.number_format;
+    let date_format = config.checked_date_format()?;
+    let title_prefix = config.title_prefix.as_str();
+    let monthly_extraction = monthy_extraction(
+        registry,
+        config.account_filter(),
+        config.date_filter(),
+        None,
+        None,
+        None,
+        None,
+    )?;
+
+    let mut order: Vec<usize> = (0..monthly_extraction.categories.len()).collect();
+    let category_total = |i: &usize| -> f64 {
+        monthly_extraction.categories_amounts[*i]
             .iter()
-            .enumerate()
-        {
-            let mut label = category_name.clone();
-            //label.push_str(&monthly_extraction.categories_amounts_perc_value.get(i).unwrap().get(j).unwrap().to_string());
-            label.push_str(&format!(
-                "{:.0}",
-                &monthly_extraction
-                    .categories_amounts_perc_value
-                    .get(i)
-                    .unwrap()
-                    .get(j)
-                    .unwrap()
-                    .abs()
-            ));
-            labels.push(label);
-        }
+            .map(|x| x.abs())
+            .sum()
+    };
+    let categories = &monthly_extraction.categories;
+    order.sort_by(|a, b| {
+        category_total(b)
+            .partial_cmp(&category_total(a))
+            .unwrap_or(Equal)
+            .then_with(|| categories[*a].cmp(&categories[*b]))
+    });
+    let pairs: Vec<&Vec<(f64, f64)>> = order
+        .iter()
+        .map(|i| &monthly_extraction.categories_pairs[*i])
+        .collect();
+    let layers = stack_category_pairs(monthly_extraction.months.len(), &pairs);
+    let y_max = layers
+        .last()
+        .map(|layer| layer.iter().map(|x| x.1).fold(0.0f64, f64::max))
+        .unwrap_or(0.0);
 
-        let mut pie = Pie::new(
-            &center,
-            &radius,
-            monthly_extraction.categories_amounts_perc.get(i).unwrap(),
-            &colors,
-            &labels, //monthly_extraction.categories_amounts_perc_names.get(i).unwrap()
-        );
+    let figure_path = config_figure_path(config, None, "monthly_stacked_area");
+    let root_area = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root_area, config)?;
+    let root_area = root_area.titled(
+        &build_title(title_prefix, "Monthly Expenses"),
+        ("sans-serif", 30),
+    )?;
 
-        pie.start_angle(66.0);
-        pie.label_style((("sans-serif", 20).into_font()).color(&(BLACK)));
-        pie.percentages((("sans-serif", radius * 0.08).into_font()).color(&BLACK));
-        da.draw(&pie)?;
+    let x_max = monthly_extraction.months_idx_range.1.max(1.0);
+    let mut chart = ChartBuilder::on(&root_area)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .margin_left(30)
+        .margin_right(30)
+        .margin_bottom(20)
+        .caption("monthly expenses per category", ("sans-serif", 20))
+        .build_cartesian_2d(
+            (0.0..x_max).step(1.0),
+            (0.0..(y_max * 1.1).max(1.0)).step(nice_step((0.0, y_max))),
+        )?;
+
+    chart
+        .configure_mesh()
+        .bold_line_style(ShapeStyle {
+            color: palette.mesh,
+            filled: false,
+            stroke_width: 1,
+        })
+        .x_labels(monthly_extraction.months.len())
+        .y_labels(20)
+        .y_label_formatter(&|x| number_format.format(*x, 0))
+        .x_label_formatter(&|x| date_label(&monthly_extraction.months, *x, date_format))
+        .y_desc("Euros")
+        .x_desc("Months")
+        .draw()?;
+
+    // Layers are drawn from the top of the stack so that each one covers the upper ones
+    for (layer, category_idx) in layers.into_iter().zip(order.iter()).rev() {
+        let color = config.category_color(&categories[*category_idx], *category_idx);
+        chart
+            .draw_series(AreaSeries::new(layer, 0.0, color.filled()).border_style(BLACK))?
+            .label(&categories[*category_idx])
+            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.filled()));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    root_area.present()?;
+    Ok(())
+}
+
+/// Plots the monthly net income as bars above or below zero
+///
+/// Months with a positive net income are green and the negative ones are red.
+pub fn plot_monthly_net_bars(
+    registry: &Registry,
+    config: &PlotConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let resolution = config.resolution;
+    let palette = &config.palette;
+    let number_format = &config.number_format;
+    let date_format = config.checked_date_format()?;
+    let title_prefix = config.title_prefix.as_str();
+    let monthly_extraction = monthy_extraction(
+        registry,
+        config.account_filter(),
+        config.date_filter(),
+        None,
+        None,
+        None,
+        None,
+    )?;
+
+    // Zero is always in the chart so that every bar starts from it
+    let y_min = monthly_extraction.net_income_range.0.min(0.0);
+    let y_max = monthly_extraction.net_income_range.1.max(0.0);
+    let margin = ((y_max - y_min) * 0.1).max(1.0);
+
+    let figure_path = config_figure_path(config, None, "monthly_net_bars");
+    let root_area = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root_area, config)?;
+    let root_area = root_area.titled(
+        &build_title(title_prefix, "Monthly Net Income"),
+        ("sans-serif", 30),
+    )?;
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .margin_left(30)
+        .margin_right(30)
+        .margin_bottom(20)
+        .build_cartesian_2d(
+            (-1.0..(monthly_extraction.months_idx_range.1 + 1.0)).step(1.0),
+            ((y_min - margin)..(y_max + margin)).step(nice_step((y_min, y_max))),
+        )?;
+
+    chart
+        .configure_mesh()
+        .bold_line_style(ShapeStyle {
+            color: palette.mesh,
+            filled: false,
+            stroke_width: 1,
+        })
+        .x_labels(monthly_extraction.months.len() + 2)
+        .y_labels(20)
+        .y_label_formatter(&|x| number_format.format(*x, 0))
+        .x_label_formatter(&|x| {
+            if *x < 0.0 {
+                String::new()
+            } else {
+                date_label(&monthly_extraction.months, *x, date_format)
+            }
+        })
+        .y_desc("Euros")
+        .x_desc("Months")
+        .draw()?;
+
+    let positive_color = RGBAColor(56, 142, 60, 1.0);
+    let negative_color = RGBAColor(198, 40, 40, 1.0);
+    chart.draw_series(
+        monthly_extraction
+            .net_income_pairs
+            .iter()
+            .map(|&(x, net_income)| {
+                let color = if net_income >= 0.0 {
+                    positive_color
+                } else {
+                    negative_color
+                };
+                Rectangle::new([(x - 0.4, 0.0), (x + 0.4, net_income)], color.filled())
+            }),
+    )?;
+
+    root_area.present()?;
+    Ok(())
+}
+
+/// Plots the gross income and the gross expenses of each month as two bars
+///
+/// The income bar is green on the left of the month and the expense bar is red
+/// on its right, both are drawn from zero.
+pub fn plot_monthly_income_expense_bars(
+    registry: &Registry,
+    config: &PlotConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let resolution = config.resolution;
+    let palette = &config.palette;
+    let number_format = &config.number_format;
+    let date_format = config.checked_date_format()?;
+    let title_prefix = config.title_prefix.as_str();
+    let income_expense =
+        extract_monthly_income_expense(registry, config.account_filter(), config.date_filter())?;
+
+    let y_max = income_expense
+        .incomes
+        .iter()
+        .chain(&income_expense.expenses)
+        .fold(0.0, |max: f64, amount| max.max(*amount));
+
+    let figure_path = config_figure_path(config, None, "monthly_income_expense_bars");
+    let root_area = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root_area, config)?;
+    let root_area = root_area.titled(
+        &build_title(title_prefix, "Monthly Income and Expenses"),
+        ("sans-serif", 30),
+    )?;
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .margin_left(30)
+        .margin_right(30)
+        .margin_bottom(20)
+        .build_cartesian_2d(
+            (-1.0..(income_expense.months.len() as f64)).step(1.0),
+            (0.0..(y_max * 1.1).max(1.0)).step(nice_step((0.0, y_max))),
+        )?;
+
+    chart
+        .configure_mesh()
+        .bold_line_style(ShapeStyle {
+            color: palette.mesh,
+            filled: false,
+            stroke_width: 1,
+        })
+        .x_labels(income_expense.months.len() + 2)
+        .y_labels(20)
+        .y_label_formatter(&|x| number_format.format(*x, 0))
+        .x_label_formatter(&|x| {
+            if *x < 0.0 {
+                String::new()
+            } else {
+                date_label(&income_expense.months, *x, date_format)
+            }
+        })
+        .y_desc("Euros")
+        .x_desc("Months")
+        .draw()?;
+
+    let income_color = RGBAColor(56, 142, 60, 1.0);
+    let expense_color = RGBAColor(198, 40, 40, 1.0);
+    for (name, amounts, offset, color) in [
+        ("Income", &income_expense.incomes, -0.4, income_color),
+        ("Expenses", &income_expense.expenses, 0.0, expense_color),
+    ] {
+        chart
+            .draw_series(amounts.iter().enumerate().map(|(x, amount)| {
+                let x = x as f64 + offset;
+                Rectangle::new([(x, 0.0), (x + 0.4, *amount)], color.filled())
+            }))?
+            .label(name)
+            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.filled()));
     }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
 
     root_area.present()?;
     Ok(())
 }
+
+/// Plots the monthly budget of each category next to its average monthly spending
+///
+/// Spending bars of the categories over budget are filled with a different color.
+pub fn plot_budget_report(
+    registry: &Registry,
+    budgets: &HashMap<TransactionCategory, f32>,
+    config: &PlotConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let resolution = config.resolution;
+    let palette = &config.palette;
+    let number_format = &config.number_format;
+    let title_prefix = config.title_prefix.as_str();
+    let budget_comparison = extract_budget_comparison(
+        registry,
+        budgets,
+        config.account_filter(),
+        config.date_filter(),
+        None,
+    )?;
+
+    let n_months = budget_comparison.months.len().max(1) as f64;
+    let average_actuals: Vec<f64> = budget_comparison
+        .actuals
+        .iter()
+        .map(|x| x.iter().sum::<f64>() / n_months)
+        .collect();
+    let y_max = budget_comparison
+        .budgets
+        .iter()
+        .chain(average_actuals.iter())
+        .copied()
+        .fold(0.0f64, f64::max);
+
+    let figure_path = config_figure_path(config, None, "budget_report");
+    let root_area = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root_area, config)?;
+    let root_area = root_area.titled(
+        &build_title(title_prefix, "Budget Report"),
+        ("sans-serif", 30),
+    )?;
+
+    let n_categories = budget_comparison.categories.len() as f64;
+    let mut chart = ChartBuilder::on(&root_area)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .margin_left(30)
+        .margin_right(30)
+        .margin_bottom(20)
+        .caption("monthly budget vs average spending", ("sans-serif", 20))
+        .build_cartesian_2d((-1.0f64..n_categories).step(1.0), 0.0f64..(y_max * 1.1))?;
+
+    chart
+        .configure_mesh()
+        .bold_line_style(ShapeStyle {
+            color: palette.mesh,
+            filled: false,
+            stroke_width: 1,
+        })
+        .x_labels(budget_comparison.categories.len() + 2)
+        .y_labels(20)
+        .y_label_formatter(&|x| number_format.format(*x, 0))
+        .x_label_formatter(&|x| {
+            if *x < 0.0 {
+                String::new()
+            } else {
+                budget_comparison
+                    .categories
+                    .get(*x as usize)
+                    .cloned()
+                    .unwrap_or_default()
+            }
+        })
+        .y_desc("Euros")
+        .x_desc("Categories")
+        .draw()?;
+
+    let budget_color = palette.color(2);
+    let under_budget_color = palette.color(0);
+    let over_budget_color = palette.color(3);
+    chart
+        .draw_series(
+            budget_comparison
+                .budgets
+                .iter()
+                .enumerate()
+                .map(|(i, budget)| {
+                    let x = i as f64;
+                    Rectangle::new([(x - 0.4, 0.0), (x, *budget)], budget_color.filled())
+                }),
+        )?
+        .label("Budget")
+        .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], budget_color.filled()));
+    chart
+        .draw_series(average_actuals.iter().enumerate().map(|(i, actual)| {
+            let x = i as f64;
+            let color = if *actual > budget_comparison.budgets[i] {
+                over_budget_color
+            } else {
+                under_budget_color
+            };
+            Rectangle::new([(x, 0.0), (x + 0.4, *actual)], color.filled())
+        }))?
+        .label("Average spending")
+        .legend(move |(x, y)| {
+            Rectangle::new([(x, y - 5), (x + 20, y + 5)], under_budget_color.filled())
+        });
+    chart
+        .draw_series(std::iter::once(Rectangle::new(
+            [(-1.0, 0.0), (-1.0, 0.0)],
+            over_budget_color.filled(),
+        )))?
+        .label("Over budget")
+        .legend(move |(x, y)| {
+            Rectangle::new([(x, y - 5), (x + 20, y + 5)], over_budget_color.filled())
+        });
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    root_area.present()?;
+    Ok(())
+}
+
+/// Plots the expenses by day of the week and month as a heatmap
+///
+/// The color of a cell goes from the background, for no expenses,
+/// to the first color of the palette for the highest expenses.
+pub fn plot_spending_heatmap(
+    registry: &Registry,
+    config: &PlotConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let resolution = config.resolution;
+    let palette = &config.palette;
+    let date_format = config.checked_date_format()?;
+    let title_prefix = config.title_prefix.as_str();
+    let matrix = extract_dow_month_matrix(
+        registry,
+        config.account_filter(),
+        config.date_filter(),
+        None,
+    )?;
+    let max_amount = matrix
+        .amounts
+        .iter()
+        .flatten()
+        .copied()
+        .fold(0.0f64, f64::max);
+
+    let figure_path = config_figure_path(config, None, "spending_heatmap");
+    let root_area = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root_area, config)?;
+    let root_area = root_area.titled(
+        &build_title(title_prefix, "Spending Heatmap"),
+        ("sans-serif", 30),
+    )?;
+
+    let n_months = matrix.months.len();
+    let n_weekdays = matrix.weekdays.len();
+    let mut chart = ChartBuilder::on(&root_area)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .margin_left(30)
+        .margin_right(30)
+        .margin_bottom(20)
+        .caption("expenses by day of the week", ("sans-serif", 20))
+        .build_cartesian_2d(
+            (0..n_months).into_segmented(),
+            (0..n_weekdays).into_segmented(),
+        )?;
+
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_labels(n_months)
+        .y_labels(n_weekdays)
+        .x_label_formatter(&|x| match x {
+            SegmentValue::CenterOf(i) => date_label(&matrix.months, *i as f64, date_format),
+            _ => String::new(),
+        })
+        .y_label_formatter(&|y| match y {
+            SegmentValue::CenterOf(i) => matrix
+                .weekdays
+                .get(*i)
+                .map(|weekday| weekday.to_string())
+                .unwrap_or_default(),
+            _ => String::new(),
+        })
+        .y_desc("Day of the week")
+        .x_desc("Months")
+        .draw()?;
+
+    let cell_color = |amount: f64| {
+        if amount > 0.0 {
+            interpolate_color(palette.background, palette.color(0), amount / max_amount)
+        } else {
+            palette.background
+        }
+    };
+    for (row, amounts) in matrix.amounts.iter().enumerate() {
+        chart.draw_series(amounts.iter().enumerate().map(|(column, amount)| {
+            let from = (SegmentValue::Exact(column), SegmentValue::Exact(row));
+            let to = (
+                SegmentValue::Exact(column + 1),
+                SegmentValue::Exact(row + 1),
+            );
+            Rectangle::new([from, to], cell_color(*amount).filled())
+        }))?;
+    }
+
+    root_area.present()?;
+    Ok(())
+}
+
+/// Plots the expenses of each day of the week as bars from Monday to Sunday
+pub fn plot_weekday_spending(
+    registry: &Registry,
+    config: &PlotConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let resolution = config.resolution;
+    let palette = &config.palette;
+    let number_format = &config.number_format;
+    let title_prefix = config.title_prefix.as_str();
+    let weekday_spending = extract_weekday_spending(
+        registry,
+        config.account_filter(),
+        config.date_filter(),
+        None,
+    )?;
+    let y_max = weekday_spending
+        .amounts
+        .iter()
+        .copied()
+        .fold(0.0f64, f64::max);
+
+    let figure_path = config_figure_path(config, None, "weekday_spending");
+    let root_area = FigureBackend::new(&figure_path, resolution).into_drawing_area();
+    fill_background(&root_area, config)?;
+    let root_area = root_area.titled(
+        &build_title(title_prefix, "Weekday Spending"),
+        ("sans-serif", 30),
+    )?;
+
+    let n_weekdays = weekday_spending.weekdays.len();
+    let mut chart = ChartBuilder::on(&root_area)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .margin_left(30)
+        .margin_right(30)
+        .margin_bottom(20)
+        .caption("expenses by day of the week", ("sans-serif", 20))
+        .build_cartesian_2d(
+            (0..n_weekdays).into_segmented(),
+            (0.0..(y_max * 1.1).max(1.0)).step(nice_step((0.0, y_max))),
+        )?;
+
+    chart
+        .configure_mesh()
+        .bold_line_style(ShapeStyle {
+            color: palette.mesh,
+            filled: false,
+            stroke_width: 1,
+        })
+        .x_labels(n_weekdays)
+        .y_labels(20)
+        .y_label_formatter(&|y| number_format.format(*y, 0))
+        .x_label_formatter(&|x| match x {
+            SegmentValue::CenterOf(i) => weekday_spending
+                .weekdays
+                .get(*i)
+                .map(|weekday| weekday.to_string())
+                .unwrap_or_default(),
+            _ => String::new(),
+        })
+        .y_desc("Euros")
+        .x_desc("Day of the week")
+        .draw()?;
+
+    let color = palette.color(0);
+    chart.draw_series(
+        weekday_spending
+            .amounts
+            .iter()
+            .enumerate()
+            .map(|(i, amount)| {
+                Rectangle::new(
+                    [
+                        (SegmentValue::Exact(i), 0.0),
+                        (SegmentValue::Exact(i + 1), *amount),
+                    ],
+                    color.filled(),
+                )
+            }),
+    )?;
+
+    root_area.present()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use plotters::style::{Color, RGBColor};
+
+    use crate::plots::plot_config::PlotConfig;
+    use crate::plots::plot_utils::palettes::CB_FRIENDLY_PALETTE;
+
+    use super::{
+        build_figure_path, build_title, date_label, pie_colors, pie_labels, slice_order,
+        stack_category_pairs, PieLabel, SliceOrder,
+    };
+
+    #[test]
+    fn build_title_prefix() {
+        assert_eq!(build_title("", "Budget Report"), "Budget Report");
+        assert_eq!(
+            build_title("Ale 2023", "Budget Report"),
+            "Ale 2023 Budget Report"
+        );
+    }
+
+    #[test]
+    fn build_figure_path_prefix() {
+        assert_eq!(
+            build_figure_path("plots", None, "transaction_pie.png"),
+            "plots/transaction_pie.png"
+        );
+        assert_eq!(
+            build_figure_path("plots", Some("2023"), "transaction_pie.png"),
+            "plots/2023_transaction_pie.png"
+        );
+    }
+
+    #[test]
+    fn date_label_formatting() {
+        let dates = vec![
+            NaiveDate::from_ymd_opt(2023, 5, 9).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+        ];
+        assert_eq!(date_label(&dates, 0.0, "%Y-%m-%d"), "2023-05-09");
+        assert_eq!(date_label(&dates, 1.0, "%b %y"), "Jan 24");
+        assert_eq!(date_label(&dates, 2.0, "%b %y"), "");
+    }
+
+    #[test]
+    fn pie_labels_formatting() {
+        let categories = vec![String::from("Spesa"), String::from("Affitto")];
+        let amounts = vec![-80.4, -600.0];
+
+        assert_eq!(
+            pie_labels(&categories, &amounts, PieLabel::Percentage),
+            categories
+        );
+        assert_eq!(
+            pie_labels(&categories, &amounts, PieLabel::Absolute),
+            vec!["Spesa 80€", "Affitto 600€"]
+        );
+        assert_eq!(
+            pie_labels(&categories, &amounts, PieLabel::Both),
+            pie_labels(&categories, &amounts, PieLabel::Absolute)
+        );
+    }
+
+    #[test]
+    fn stacked_category_layers() {
+        let first = vec![(0.0, -10.0), (2.0, -30.0)];
+        let second = vec![(1.0, -5.0), (2.0, -1.0)];
+
+        assert_eq!(
+            stack_category_pairs(3, &[&first, &second]),
+            vec![
+                vec![(0.0, 10.0), (1.0, 0.0), (2.0, 30.0)],
+                vec![(0.0, 10.0), (1.0, 5.0), (2.0, 31.0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn slice_colors_by_category() {
+        let january: Vec<String> = ["Spesa", "Affitto", "Other"].map(String::from).to_vec();
+        let february: Vec<String> = ["Bolletta", "Other", "Spesa", "Affitto"]
+            .map(String::from)
+            .to_vec();
+
+        assert_eq!(slice_order(&january, SliceOrder::BySize), [0, 1, 2]);
+        assert_eq!(slice_order(&january, SliceOrder::ByName), [1, 0, 2]);
+        assert_eq!(slice_order(&february, SliceOrder::ByName), [3, 0, 2, 1]);
+
+        // the same category has the same color in both months, whatever its slice
+        let colors = |categories: &[String]| -> Vec<(String, (u8, u8, u8))> {
+            let ordered: Vec<String> = slice_order(categories, SliceOrder::ByName)
+                .into_iter()
+                .map(|i| categories[i].clone())
+                .collect();
+            let colors = pie_colors(&ordered, &PlotConfig::default());
+            ordered
+                .into_iter()
+                .zip(colors)
+                .map(|(category, color)| (category, (color.0, color.1, color.2)))
+                .collect()
+        };
+        let january_colors = colors(&january);
+        let february_colors = colors(&february);
+        for (category, color) in &january_colors {
+            assert!(
+                february_colors.contains(&(category.clone(), *color)),
+                "{category}"
+            );
+        }
+        assert_ne!(january_colors[0].1, january_colors[1].1);
+    }
+
+    #[test]
+    fn distinct_pie_colors() {
+        // multi-word categories are named by their variant, Affitto and Uscite
+        // prefer the same color of the shorter palette
+        let categories: Vec<String> = [
+            "Uscite",
+            "CartaDiCredito",
+            "PranzoLavoro",
+            "Banca",
+            "RitiroBancomat",
+            "Affitto",
+            "Sanita",
+            "Other",
+        ]
+        .map(String::from)
+        .to_vec();
+        let config = PlotConfig::default().palette(CB_FRIENDLY_PALETTE);
+
+        let colors = pie_colors(&categories, &config);
+        for (i, color) in colors.iter().enumerate() {
+            assert!(!colors[..i].contains(color), "{}", categories[i]);
+        }
+        // the earlier category keeps its color
+        let (r, g, b) = CB_FRIENDLY_PALETTE.color(0).rgb();
+        assert_eq!(colors[5], RGBColor(r, g, b));
+        assert_eq!(pie_colors(&categories[5..6], &config), [RGBColor(r, g, b)]);
+        assert_eq!(pie_colors(&categories[..1], &config), [RGBColor(r, g, b)]);
+    }
+}
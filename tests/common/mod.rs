@@ -0,0 +1,39 @@
+//! Fixtures shared by the integration tests
+#![allow(dead_code)]
+
+use chrono::NaiveDate;
+use realearning::model::{
+    account::TransactionAccountName,
+    registry::Registry,
+    transaction::{TransactionCategory, TransactionEvent},
+};
+
+pub fn date(date: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+}
+
+pub fn transaction(date_str: &str, amount: f32, category: TransactionCategory) -> TransactionEvent {
+    TransactionEvent::new(
+        date(date_str),
+        amount,
+        category,
+        None,
+        TransactionAccountName::Ale,
+    )
+}
+
+/// Registry over two months with both incomes and expenses
+pub fn sample_registry() -> Registry {
+    Registry::from_transactions(
+        vec![
+            transaction("2023-01-01", 1500.0, TransactionCategory::Stipendio),
+            transaction("2023-01-03", -600.0, TransactionCategory::Affitto),
+            transaction("2023-01-10", -80.5, TransactionCategory::Spesa),
+            transaction("2023-02-01", 1500.0, TransactionCategory::Stipendio),
+            transaction("2023-02-02", 50.0, TransactionCategory::Regalo),
+            transaction("2023-02-03", -600.0, TransactionCategory::Affitto),
+            transaction("2023-02-15", -42.0, TransactionCategory::Treno),
+        ],
+        None,
+    )
+}
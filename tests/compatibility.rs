@@ -0,0 +1,360 @@
+use std::{collections::HashMap, path::Path};
+
+use assert_fs::{prelude::*, TempDir};
+use chrono::NaiveDate;
+use indicatif::MultiProgress;
+use realearning::{
+    compatibility::{
+        compatibility_errors::ExtractionError,
+        input_paths, ofx,
+        registro_ale::{
+            build_registry, build_registry_batch, build_registry_batch_parallel,
+            build_registry_files, spreadsheet_paths,
+        },
+        AmountColumns, CompatibilityEnum, MonthCheck, SignConvention,
+    },
+    model::{account::TransactionAccountName, transaction::TransactionCategory},
+};
+use regex::Regex;
+
+const WORKBOOK: &str = "tests/fixtures/registro_ale.xlsx";
+
+fn worksheet_template() -> Regex {
+    Regex::new(r"^\d{4}-\d{2}$").unwrap()
+}
+
+#[test]
+fn missing_workbook() {
+    let result = build_registry_batch(
+        "tests/fixtures/missing.xlsx",
+        worksheet_template(),
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+    );
+
+    let error = result.err().unwrap();
+    assert_eq!(error.path, "tests/fixtures/missing.xlsx");
+    assert!(error.to_string().starts_with("failed to open"));
+}
+
+#[test]
+fn failed_sheet() {
+    let (registry, failed_extractions) = build_registry_batch(
+        WORKBOOK,
+        worksheet_template(),
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+    )
+    .unwrap();
+
+    let failed_names: Vec<&str> = failed_extractions.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(failed_names, vec!["2023-04"]);
+    assert_eq!(registry.iter().count(), 8);
+}
+
+#[test]
+fn missing_accounts_table() {
+    let (registry, failed_extractions) = build_registry_batch(
+        "tests/fixtures/registro_ale_no_accounts.xlsx",
+        worksheet_template(),
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+    )
+    .unwrap();
+
+    assert!(failed_extractions.is_empty());
+    assert_eq!(registry.iter().count(), 2);
+    let mut accounts = registry.get_accounts();
+    accounts.sort();
+    assert_eq!(accounts, vec!["Ale", "Giulia"]);
+}
+
+#[test]
+fn duplicate_columns() {
+    let error = build_registry(
+        "tests/fixtures/registro_ale_duplicate_columns.xlsx",
+        "2023-06",
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+        &MultiProgress::new(),
+    )
+    .err()
+    .unwrap();
+
+    assert_eq!(
+        error.downcast_ref::<ExtractionError>(),
+        Some(&ExtractionError::DuplicateColumn(String::from("Nota")))
+    );
+}
+
+#[test]
+fn parallel_extraction() {
+    let (sequential, sequential_failed) = build_registry_batch(
+        WORKBOOK,
+        worksheet_template(),
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+    )
+    .unwrap();
+    let (parallel, parallel_failed) = build_registry_batch_parallel(
+        WORKBOOK,
+        worksheet_template(),
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+    )
+    .unwrap();
+
+    assert_eq!(sequential_failed, parallel_failed);
+    assert!(sequential
+        .to_dataframe()
+        .unwrap()
+        .frame_equal_missing(&parallel.to_dataframe().unwrap()));
+
+    let mut accounts = sequential.get_accounts();
+    accounts.sort();
+    let mut parallel_accounts = parallel.get_accounts();
+    parallel_accounts.sort();
+    assert_eq!(accounts, parallel_accounts);
+    for account in accounts {
+        let account = vec![account];
+        assert_eq!(
+            sequential.get_initial_account_values(Some(&account)),
+            parallel.get_initial_account_values(Some(&account))
+        );
+    }
+}
+
+#[test]
+fn multiple_files() {
+    let paths = vec![
+        String::from(WORKBOOK),
+        String::from("tests/fixtures/registro_ale_no_accounts.xlsx"),
+    ];
+    let (registry, failed_extractions) = build_registry_files(
+        &paths,
+        worksheet_template(),
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+    )
+    .unwrap();
+
+    assert_eq!(registry.iter().count(), 8 + 2);
+    let failed_names: Vec<(&str, Vec<&str>)> = failed_extractions
+        .iter()
+        .map(|(path, failed)| {
+            (
+                path.as_str(),
+                failed.iter().map(|x| x.name.as_str()).collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        failed_names,
+        vec![
+            (WORKBOOK, vec!["2023-04"]),
+            ("tests/fixtures/registro_ale_no_accounts.xlsx", vec![])
+        ]
+    );
+
+    let missing = vec![
+        String::from(WORKBOOK),
+        String::from("tests/fixtures/missing.xlsx"),
+    ];
+    let error = build_registry_files(
+        &missing,
+        worksheet_template(),
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+    )
+    .err()
+    .unwrap();
+    assert_eq!(error.path, "tests/fixtures/missing.xlsx");
+}
+
+#[test]
+fn input_dir() {
+    let dir = TempDir::new().unwrap();
+    dir.child("2023.xlsx")
+        .write_file(Path::new(WORKBOOK))
+        .unwrap();
+    dir.child("2022.XLSX")
+        .write_file(Path::new("tests/fixtures/registro_ale_no_accounts.xlsx"))
+        .unwrap();
+    dir.child("~$2023.xlsx").write_str("lock").unwrap();
+    dir.child("notes.txt").write_str("notes").unwrap();
+    dir.child("archive.xlsx").create_dir_all().unwrap();
+
+    let paths = spreadsheet_paths(dir.path().to_str().unwrap()).unwrap();
+    let file_names: Vec<&str> = paths
+        .iter()
+        .map(|path| Path::new(path).file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(file_names, vec!["2022.XLSX", "2023.xlsx"]);
+
+    let (registry, _) = build_registry_files(
+        &paths,
+        worksheet_template(),
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+    )
+    .unwrap();
+    assert_eq!(registry.iter().count(), 2 + 8);
+
+    assert!(spreadsheet_paths("tests/fixtures/missing").is_err());
+
+    // a directory without files of the compatibility
+    let error = input_paths(
+        dir.path().to_str().unwrap(),
+        CompatibilityEnum::Ofx.file_extensions(),
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+
+    dir.child("2023-05.OFX").write_str("statement").unwrap();
+    dir.child("2023-06.qfx").write_str("statement").unwrap();
+    let paths = input_paths(
+        dir.path().to_str().unwrap(),
+        CompatibilityEnum::Ofx.file_extensions(),
+    )
+    .unwrap();
+    let file_names: Vec<&str> = paths
+        .iter()
+        .map(|path| Path::new(path).file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(file_names, vec!["2023-05.OFX", "2023-06.qfx"]);
+}
+
+#[test]
+fn inverted_amounts() {
+    let (registry, _) = build_registry_batch(
+        WORKBOOK,
+        worksheet_template(),
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+    )
+    .unwrap();
+    let (inverted, _) = build_registry_batch(
+        WORKBOOK,
+        worksheet_template(),
+        SignConvention::IncomeNegative,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+    )
+    .unwrap();
+
+    let net_income: f32 = registry.iter().map(|t| t.amount).sum();
+    let inverted_net_income: f32 = inverted.iter().map(|t| t.amount).sum();
+    assert!(net_income > 0.0);
+    assert_eq!(inverted_net_income, -net_income);
+}
+
+#[test]
+fn debit_credit_columns() {
+    let amount_columns = AmountColumns::DebitCredit {
+        debit: String::from("Uscite"),
+        credit: String::from("Entrate"),
+    };
+    let registry = build_registry(
+        "tests/fixtures/registro_ale_debit_credit.xlsx",
+        "2023-05",
+        SignConvention::IncomePositive,
+        &amount_columns,
+        MonthCheck::Error,
+        &HashMap::new(),
+        &MultiProgress::new(),
+    )
+    .unwrap();
+
+    let amounts: Vec<f32> = registry.iter().map(|t| t.amount).collect();
+    assert_eq!(amounts, vec![1500.0, -25.0, -6.0]);
+
+    // the signed amount column is missing
+    assert!(build_registry(
+        "tests/fixtures/registro_ale_debit_credit.xlsx",
+        "2023-05",
+        SignConvention::IncomePositive,
+        &AmountColumns::default(),
+        MonthCheck::Skip,
+        &HashMap::new(),
+        &MultiProgress::new(),
+    )
+    .is_err());
+}
+
+#[test]
+fn month_check_error() {
+    let extract = |month_check| {
+        build_registry(
+            "tests/fixtures/registro_ale_wrong_month.xlsx",
+            "2023-05",
+            SignConvention::IncomePositive,
+            &AmountColumns::default(),
+            month_check,
+            &HashMap::new(),
+            &MultiProgress::new(),
+        )
+    };
+
+    assert_eq!(extract(MonthCheck::Skip).unwrap().iter().count(), 2);
+    let error = extract(MonthCheck::Error).err().unwrap();
+    assert_eq!(
+        error.downcast_ref::<ExtractionError>(),
+        Some(&ExtractionError::DateOutsideMonth(
+            NaiveDate::from_ymd_opt(2023, 4, 4).unwrap()
+        ))
+    );
+}
+
+#[test]
+fn ofx_statement() {
+    let account_ids = HashMap::from([(
+        String::from("4000123412341234"),
+        TransactionAccountName::CartaAle,
+    )]);
+    let registry = ofx::build_registry("tests/fixtures/statement.ofx", &account_ids).unwrap();
+
+    let transactions: Vec<(NaiveDate, f32, Option<&str>)> = registry
+        .iter()
+        .map(|t| (t.date, t.amount, t.description.as_deref()))
+        .collect();
+    assert_eq!(
+        transactions,
+        vec![
+            (
+                NaiveDate::from_ymd_opt(2023, 5, 3).unwrap(),
+                -42.0,
+                Some("TRENORD")
+            ),
+            (
+                NaiveDate::from_ymd_opt(2023, 5, 10).unwrap(),
+                12.5,
+                Some("RIMBORSO - Reso ordine")
+            ),
+        ]
+    );
+    assert_eq!(registry.get_accounts(), vec!["carta ale"]);
+    assert!(registry
+        .iter()
+        .all(|t| t.category == TransactionCategory::Uncategorized));
+}
@@ -0,0 +1,727 @@
+mod common;
+
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate, Weekday};
+use common::{date, sample_registry, transaction};
+use realearning::{
+    io::categories_config::{CategoriesConfig, CategoriesConfigError},
+    model::{
+        account::{Account, TransactionAccountName},
+        registry::Registry,
+        transaction::{TransactionCategory, TransactionEvent},
+    },
+    plots::{
+        extraction::{
+            extract_accounts_cumulative, extract_budget_comparison, extract_categories_split,
+            extract_category_trends, extract_daily_transactions, extract_dow_month_matrix,
+            extract_low_balance_days, extract_month_end_projection, extract_monthly_income_expense,
+            extract_net_by_period, extract_weekday_spending, linear_regression_slope,
+            monthy_extraction, savings_rate, Period, WeekStart,
+        },
+        plot_errors::PlotError,
+    },
+};
+
+#[test]
+fn categories_split_groups_other() {
+    let mut registry = sample_registry();
+    registry.add_batch(vec![
+        transaction("2023-02-20", -15.0, TransactionCategory::Pasto),
+        transaction("2023-02-21", -10.0, TransactionCategory::Telefono),
+        transaction("2023-02-22", 20.0, TransactionCategory::Varie),
+    ]);
+
+    let split = extract_categories_split(&registry, None, None, None, Some(2)).unwrap();
+
+    assert_eq!(split.expense_categories, vec!["Affitto", "Spesa", "Other"]);
+    assert_eq!(
+        split.income_categories,
+        vec!["Stipendio", "Regalo", "Other"]
+    );
+    assert_eq!(split.expense_counts, vec![2, 1, 3]);
+    assert_eq!(split.income_counts, vec![2, 1, 1]);
+    assert!((split.expense_means[2] - 67.0 / 3.0).abs() < 1e-6);
+    assert!((split.expense_amounts[2] + 67.0).abs() < 1e-6);
+    assert!((split.income_amounts[2] - 20.0).abs() < 1e-6);
+    assert!((split.expense_percentages.iter().sum::<f64>() - 100.0).abs() < 1e-6);
+    assert!((split.income_percentages.iter().sum::<f64>() - 100.0).abs() < 1e-6);
+}
+
+#[test]
+fn categories_split_without_limit() {
+    let registry: Registry = sample_registry();
+    let split = extract_categories_split(&registry, None, None, None, Some(10)).unwrap();
+
+    assert!(!split.expense_categories.contains(&String::from("Other")));
+    assert_eq!(split.expense_categories.len(), 3);
+    assert_eq!(split.income_categories.len(), 2);
+}
+
+#[test]
+fn categories_split_percentages() {
+    let registry = sample_registry();
+    let split = extract_categories_split(&registry, None, None, None, None).unwrap();
+
+    // Expenses: Affitto 1200, Spesa 80.5, Treno 42 over 1322.5
+    assert_eq!(split.expense_categories, vec!["Affitto", "Spesa", "Treno"]);
+    let expected_expenses = [1200.0 / 1322.5, 80.5 / 1322.5, 42.0 / 1322.5];
+    for (perc, expected) in split.expense_percentages.iter().zip(expected_expenses) {
+        assert!(*perc > 0.0);
+        assert!((perc - expected * 100.0).abs() < 1e-6);
+    }
+    assert!(split.expense_amounts.iter().all(|x| *x < 0.0));
+
+    // Incomes: Stipendio 3000, Regalo 50 over 3050
+    assert_eq!(split.income_categories, vec!["Stipendio", "Regalo"]);
+    let expected_incomes = [3000.0 / 3050.0, 50.0 / 3050.0];
+    for (perc, expected) in split.income_percentages.iter().zip(expected_incomes) {
+        assert!((perc - expected * 100.0).abs() < 1e-6);
+    }
+    assert!(split.income_amounts.iter().all(|x| *x > 0.0));
+}
+
+#[test]
+fn budget_comparison() {
+    let mut registry = sample_registry();
+    registry.add_batch(vec![transaction(
+        "2023-02-08",
+        -45.0,
+        TransactionCategory::PranzoLavoro,
+    )]);
+    let budgets = HashMap::from([
+        (TransactionCategory::Affitto, 550.0),
+        (TransactionCategory::Spesa, 100.0),
+        (TransactionCategory::PranzoLavoro, 40.0),
+    ]);
+
+    let comparison = extract_budget_comparison(&registry, &budgets, None, None, None).unwrap();
+
+    assert_eq!(
+        comparison.months,
+        vec![date("2023-01-01"), date("2023-02-01")]
+    );
+    assert_eq!(
+        comparison.categories,
+        vec!["Affitto", "PranzoLavoro", "Spesa"]
+    );
+    assert_eq!(comparison.budgets, vec![550.0, 40.0, 100.0]);
+    // Affitto is over budget every month
+    assert_eq!(comparison.actuals[0], vec![600.0, 600.0]);
+    assert_eq!(comparison.differences[0], vec![-50.0, -50.0]);
+    // PranzoLavoro is matched by its serialized name and is over budget in February
+    assert_eq!(comparison.actuals[1], vec![0.0, 45.0]);
+    assert_eq!(comparison.differences[1], vec![40.0, -5.0]);
+    // Spesa is under budget and has no expenses in February
+    assert_eq!(comparison.actuals[2], vec![80.5, 0.0]);
+    assert_eq!(comparison.differences[2], vec![19.5, 100.0]);
+}
+
+#[test]
+fn daily_cumulative_sum_precision() {
+    let start = date("2023-01-01");
+    let mut registry = Registry::new(None);
+    registry.add_batch(
+        (0..10_000)
+            .map(|i| {
+                let day = (start + Duration::days(i % 100)).to_string();
+                transaction(&day, 0.01, TransactionCategory::Spesa)
+            })
+            .collect(),
+    );
+
+    let daily_transactions =
+        extract_daily_transactions(&registry, None, None, None, None, false, None).unwrap();
+
+    let reference: f64 = (0..10_000).map(|_| 0.01f64).sum();
+    let total = *daily_transactions.cumsum_amounts.last().unwrap();
+    assert!((total - reference).abs() < 1e-9);
+}
+
+fn assert_no_data<T>(result: Result<T, Box<dyn std::error::Error>>) {
+    match result {
+        Ok(_) => panic!("expected PlotError::NoData"),
+        Err(e) => assert_eq!(e.downcast_ref::<PlotError>(), Some(&PlotError::NoData)),
+    }
+}
+
+#[test]
+fn empty_date_range() {
+    let registry = sample_registry();
+    let (from, to) = (date("2022-01-01"), date("2022-12-31"));
+    let date_range = Some((&from, &to));
+
+    assert_no_data(extract_daily_transactions(
+        &registry, None, date_range, None, None, true, None,
+    ));
+    assert_no_data(extract_categories_split(
+        &registry, None, date_range, None, None,
+    ));
+    assert_no_data(monthy_extraction(
+        &registry, None, date_range, None, None, None, None,
+    ));
+    assert_no_data(extract_budget_comparison(
+        &registry,
+        &HashMap::from([(TransactionCategory::Spesa, 100.0)]),
+        None,
+        date_range,
+        None,
+    ));
+}
+
+#[test]
+fn empty_registry() {
+    let registry = Registry::new(None);
+
+    assert_no_data(extract_daily_transactions(
+        &registry, None, None, None, None, true, None,
+    ));
+    assert_no_data(extract_categories_split(&registry, None, None, None, None));
+    assert_no_data(monthy_extraction(
+        &registry, None, None, None, None, None, None,
+    ));
+}
+
+#[test]
+fn categories_split_counts() {
+    let registry = sample_registry();
+    let split = extract_categories_split(&registry, None, None, None, None).unwrap();
+
+    assert_eq!(split.expense_categories, vec!["Affitto", "Spesa", "Treno"]);
+    assert_eq!(split.expense_counts, vec![2, 1, 1]);
+    assert_eq!(split.income_categories, vec!["Stipendio", "Regalo"]);
+    assert_eq!(split.income_counts, vec![2, 1]);
+}
+
+#[test]
+fn categories_split_expenses_only() {
+    let mut registry = Registry::new(None);
+    registry.add_batch(vec![
+        transaction("2023-01-03", -600.0, TransactionCategory::Affitto),
+        transaction("2023-01-10", -80.5, TransactionCategory::Spesa),
+    ]);
+
+    let split = extract_categories_split(&registry, None, None, None, Some(1)).unwrap();
+
+    assert!(split.income_categories.is_empty());
+    assert!(split.income_percentages.is_empty());
+    assert!(split.income_amounts.is_empty());
+    assert!(split.income_counts.is_empty());
+    assert!(split.income_means.is_empty());
+    assert_eq!(split.expense_categories, vec!["Affitto", "Other"]);
+    assert!((split.expense_percentages.iter().sum::<f64>() - 100.0).abs() < 1e-6);
+}
+
+#[test]
+fn categories_split_json() {
+    let split = extract_categories_split(&sample_registry(), None, None, None, None).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&serde_json::to_string(&split).unwrap()).unwrap();
+
+    assert_eq!(
+        json["expense_categories"],
+        serde_json::json!(["Affitto", "Spesa", "Treno"])
+    );
+    assert_eq!(json["income_counts"], serde_json::json!([2, 1]));
+}
+
+#[test]
+fn categories_split_zero_amount() {
+    let mut registry = sample_registry();
+    registry.add_batch(vec![transaction(
+        "2023-02-16",
+        0.0,
+        TransactionCategory::Varie,
+    )]);
+    let split = extract_categories_split(&registry, None, None, None, None).unwrap();
+
+    assert_eq!(split.zero_count, 1);
+    assert!(!split.income_categories.contains(&String::from("Varie")));
+    assert!(!split.expense_categories.contains(&String::from("Varie")));
+    // every transaction is on a side or counted as zero
+    let counted: usize = split.income_counts.iter().sum::<usize>()
+        + split.expense_counts.iter().sum::<usize>()
+        + split.zero_count;
+    assert_eq!(counted, registry.iter().count());
+    assert_eq!(
+        extract_categories_split(&sample_registry(), None, None, None, None)
+            .unwrap()
+            .zero_count,
+        0
+    );
+}
+
+#[test]
+fn categories_split_means() {
+    let mut registry = sample_registry();
+    registry.add_batch(vec![transaction(
+        "2023-02-16",
+        -19.5,
+        TransactionCategory::Spesa,
+    )]);
+    let split = extract_categories_split(&registry, None, None, None, None).unwrap();
+
+    assert_eq!(split.expense_categories, vec!["Affitto", "Spesa", "Treno"]);
+    assert_eq!(split.expense_means, vec![600.0, 50.0, 42.0]);
+    assert_eq!(split.income_categories, vec!["Stipendio", "Regalo"]);
+    assert_eq!(split.income_means, vec![1500.0, 50.0]);
+}
+
+#[test]
+fn dow_month_matrix() {
+    let mut registry = sample_registry();
+    registry.add_batch(vec![transaction(
+        "2023-04-04",
+        -10.0,
+        TransactionCategory::Spesa,
+    )]);
+    let matrix = extract_dow_month_matrix(&registry, None, None, None).unwrap();
+
+    assert_eq!(
+        matrix.months,
+        vec![
+            date("2023-01-01"),
+            date("2023-02-01"),
+            date("2023-03-01"),
+            date("2023-04-01")
+        ]
+    );
+    assert_eq!(matrix.weekdays.len(), 7);
+    assert_eq!(matrix.weekdays[1], Weekday::Tue);
+    assert_eq!(matrix.amounts.len(), 7);
+    assert!(matrix.amounts.iter().all(|row| row.len() == 4));
+    // 2023-01-03 and 2023-01-10 are Tuesdays
+    assert_eq!(matrix.amounts[1][0], 680.5);
+    assert_eq!(matrix.amounts[4][1], 600.0);
+    assert!(matrix.amounts.iter().all(|row| row[2] == 0.0));
+    assert_eq!(
+        matrix.amounts.iter().flatten().sum::<f64>(),
+        600.0 + 80.5 + 600.0 + 42.0 + 10.0
+    );
+}
+
+#[test]
+fn weekday_spending() {
+    let mut registry = sample_registry();
+    registry.add_batch(vec![transaction(
+        "2023-04-04",
+        -10.0,
+        TransactionCategory::Spesa,
+    )]);
+    let spending = extract_weekday_spending(&registry, None, None, None).unwrap();
+
+    assert_eq!(spending.weekdays.len(), 7);
+    assert_eq!(spending.weekdays[0], Weekday::Mon);
+    assert_eq!(spending.amounts.len(), 7);
+    // 2023-01-03, 2023-01-10 and 2023-04-04 are Tuesdays
+    assert_eq!(spending.amounts[1], 690.5);
+    // There are no expenses on Sunday
+    assert_eq!(spending.amounts[6], 0.0);
+    assert_eq!(
+        spending.amounts.iter().sum::<f64>(),
+        600.0 + 80.5 + 600.0 + 42.0 + 10.0
+    );
+}
+
+#[test]
+fn accounts_cumulative_sum_to_aggregate() {
+    let mut registry = sample_registry();
+    registry.add_batch(vec![
+        TransactionEvent::new(
+            date("2023-01-05"),
+            300.0,
+            TransactionCategory::Regalo,
+            None,
+            TransactionAccountName::Giulia,
+        ),
+        TransactionEvent::new(
+            date("2023-02-20"),
+            -120.0,
+            TransactionCategory::Spesa,
+            None,
+            TransactionAccountName::Giulia,
+        ),
+    ]);
+    let daily = extract_daily_transactions(&registry, None, None, None, None, true, None).unwrap();
+    let accounts = extract_accounts_cumulative(&registry, None, None, None, true).unwrap();
+
+    assert_eq!(accounts.accounts, vec!["Ale", "Giulia"]);
+    assert_eq!(accounts.days, daily.days);
+    for (day, total) in daily.cumsum_amounts.iter().enumerate() {
+        let stacked: f64 = accounts.cumsum_amounts.iter().map(|x| x[day]).sum();
+        assert!((stacked - total).abs() < 1e-6);
+    }
+    assert_eq!(*accounts.cumsum_amounts[1].last().unwrap(), 480.0);
+}
+
+#[test]
+fn description_filter() {
+    let mut registry = sample_registry();
+    let described = |date_str: &str, amount: f32, description: &str| {
+        TransactionEvent::new(
+            date(date_str),
+            amount,
+            TransactionCategory::Varie,
+            Some(String::from(description)),
+            TransactionAccountName::Ale,
+        )
+    };
+    registry.add_batch(vec![
+        described("2023-01-12", -25.0, "AMAZON Marketplace"),
+        described("2023-02-12", -9.0, "Abbonamento amazon prime"),
+        described("2023-02-13", -30.0, "Esselunga"),
+    ]);
+
+    let split = extract_categories_split(&registry, None, None, Some("Amazon"), None).unwrap();
+    assert_eq!(split.expense_categories, vec!["Varie"]);
+    assert_eq!(split.expense_counts, vec![2]);
+    assert!((split.expense_amounts[0] + 34.0).abs() < 1e-6);
+    assert!(split.income_categories.is_empty());
+
+    let all = extract_categories_split(&registry, None, None, None, None).unwrap();
+    assert_eq!(all.expense_counts.iter().sum::<usize>(), 7);
+}
+
+#[test]
+fn category_filter() {
+    let registry = sample_registry();
+    let categories = vec![TransactionCategory::Affitto];
+
+    let monthly =
+        monthy_extraction(&registry, None, None, None, Some(&categories), None, None).unwrap();
+    assert_eq!(monthly.categories, vec!["Affitto"]);
+    assert_eq!(monthly.net_income, vec![-600.0, -600.0]);
+
+    let daily =
+        extract_daily_transactions(&registry, None, None, None, Some(&categories), false, None)
+            .unwrap();
+    assert_eq!(daily.amounts.iter().filter(|x| **x != 0.0).count(), 2);
+    assert_eq!(*daily.cumsum_amounts.last().unwrap(), -1200.0);
+
+    // the serialized name of multi-word categories differs from the display one
+    let mut registry = sample_registry();
+    registry.add_batch(vec![
+        transaction("2023-01-12", -12.5, TransactionCategory::PranzoLavoro),
+        transaction("2023-02-12", -30.0, TransactionCategory::CartaDiCredito),
+    ]);
+    let categories = vec![
+        TransactionCategory::PranzoLavoro,
+        TransactionCategory::CartaDiCredito,
+    ];
+    let monthly =
+        monthy_extraction(&registry, None, None, None, Some(&categories), None, None).unwrap();
+    assert_eq!(monthly.categories, vec!["CartaDiCredito", "PranzoLavoro"]);
+    assert_eq!(monthly.net_income, vec![-12.5, -30.0]);
+}
+
+#[test]
+fn monthly_income_expense() {
+    let mut registry = sample_registry();
+    // March has only an expense and its income is zero
+    registry.add_batch(vec![transaction(
+        "2023-03-10",
+        -25.0,
+        TransactionCategory::Spesa,
+    )]);
+
+    let income_expense = extract_monthly_income_expense(&registry, None, None).unwrap();
+    assert_eq!(
+        income_expense.months,
+        vec![date("2023-01-01"), date("2023-02-01"), date("2023-03-01")]
+    );
+    assert_eq!(income_expense.incomes, vec![1500.0, 1550.0, 0.0]);
+    assert_eq!(income_expense.expenses, vec![680.5, 642.0, 25.0]);
+
+    assert_no_data(extract_monthly_income_expense(
+        &Registry::new(None),
+        None,
+        None,
+    ));
+}
+
+fn net_by_period(period: Period) -> Vec<(NaiveDate, f64)> {
+    let net = extract_net_by_period(&sample_registry(), period, None, None).unwrap();
+    net.periods.into_iter().zip(net.net_amounts).collect()
+}
+
+#[test]
+fn net_by_day() {
+    let registry = sample_registry();
+    let expected: Vec<(NaiveDate, f64)> = registry
+        .iter()
+        .map(|t| (t.date, f64::from(t.amount)))
+        .collect();
+    assert_eq!(net_by_period(Period::Day), expected);
+}
+
+#[test]
+fn net_by_week() {
+    assert_eq!(
+        net_by_period(Period::Week(WeekStart::Monday)),
+        vec![
+            (date("2022-12-26"), 1500.0),
+            (date("2023-01-02"), -600.0),
+            (date("2023-01-09"), -80.5),
+            (date("2023-01-30"), 950.0),
+            (date("2023-02-13"), -42.0),
+        ]
+    );
+}
+
+#[test]
+fn week_start_boundary() {
+    let mut registry = Registry::new(None);
+    registry.add_batch(vec![
+        transaction("2022-12-31", -10.0, TransactionCategory::Spesa),
+        transaction("2023-01-01", -20.0, TransactionCategory::Spesa),
+        transaction("2023-01-02", -40.0, TransactionCategory::Spesa),
+    ]);
+    let net_by_week = |start: WeekStart| -> Vec<(NaiveDate, f64)> {
+        let net = extract_net_by_period(&registry, Period::Week(start), None, None).unwrap();
+        net.periods.into_iter().zip(net.net_amounts).collect()
+    };
+
+    // Sunday 2023-01-01 closes the week started on Monday 2022-12-26
+    assert_eq!(
+        net_by_week(WeekStart::Monday),
+        vec![(date("2022-12-26"), -30.0), (date("2023-01-02"), -40.0)]
+    );
+    // Sunday 2023-01-01 opens a new week while Saturday 2022-12-31 closes the previous one
+    assert_eq!(
+        net_by_week(WeekStart::Sunday),
+        vec![(date("2022-12-25"), -10.0), (date("2023-01-01"), -60.0)]
+    );
+}
+
+#[test]
+fn category_trends() {
+    let mut registry = Registry::new(None);
+    registry.add_batch(vec![
+        transaction("2023-01-05", -10.0, TransactionCategory::Spesa),
+        transaction("2023-02-05", -20.0, TransactionCategory::Spesa),
+        transaction("2023-03-05", -30.0, TransactionCategory::Spesa),
+        transaction("2023-01-10", -100.0, TransactionCategory::Pasto),
+        transaction("2023-02-10", -80.0, TransactionCategory::Pasto),
+        transaction("2023-03-10", -60.0, TransactionCategory::Pasto),
+        transaction("2023-02-15", -42.0, TransactionCategory::Treno),
+    ]);
+    let monthly = monthy_extraction(&registry, None, None, None, None, None, None).unwrap();
+
+    let trends = extract_category_trends(&monthly);
+    let slopes: HashMap<String, Option<f64>> =
+        trends.categories.into_iter().zip(trends.slopes).collect();
+    assert!((slopes["Spesa"].unwrap() - 10.0).abs() < 1e-9);
+    assert!((slopes["Pasto"].unwrap() + 20.0).abs() < 1e-9);
+    assert_eq!(slopes["Treno"], None);
+
+    assert_eq!(linear_regression_slope(&[]), None);
+    assert_eq!(
+        linear_regression_slope(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)]),
+        Some(2.0)
+    );
+}
+
+#[test]
+fn month_end_projection() {
+    let mut registry = Registry::new(None);
+    // a constant delta of -10 a day from March 1st to March 20th
+    registry.add_batch(
+        (1..=20)
+            .map(|day| {
+                transaction(
+                    &format!("2023-03-{day:02}"),
+                    -10.0,
+                    TransactionCategory::Spesa,
+                )
+            })
+            .collect(),
+    );
+    let daily =
+        extract_daily_transactions(&registry, None, None, None, None, false, Some(1000.0)).unwrap();
+
+    let projection = extract_month_end_projection(&daily, 7).unwrap();
+    assert_eq!(projection.date, date("2023-03-31"));
+    assert_eq!(projection.daily_delta, -10.0);
+    // 800 on March 20th minus 10 for each of the 11 days left
+    assert_eq!(projection.balance, 690.0);
+
+    // the window is shortened to the available days
+    let projection = extract_month_end_projection(&daily, 100).unwrap();
+    assert_eq!(projection.balance, 690.0);
+    assert!(matches!(
+        extract_month_end_projection(&daily, 0),
+        Err(PlotError::InvalidParameter(_))
+    ));
+}
+
+#[test]
+fn net_by_month() {
+    assert_eq!(
+        net_by_period(Period::Month),
+        vec![(date("2023-01-01"), 819.5), (date("2023-02-01"), 908.0)]
+    );
+    let monthly =
+        monthy_extraction(&sample_registry(), None, None, None, None, None, None).unwrap();
+    assert_eq!(monthly.net_income, vec![819.5, 908.0]);
+}
+
+#[test]
+fn net_by_year() {
+    assert_eq!(
+        net_by_period(Period::Year(1)),
+        vec![(date("2023-01-01"), 1727.5)]
+    );
+}
+
+#[test]
+fn net_by_fiscal_year() {
+    let mut registry = Registry::new(None);
+    registry.add_batch(vec![
+        transaction("2023-03-31", -10.0, TransactionCategory::Spesa),
+        transaction("2023-04-01", -20.0, TransactionCategory::Spesa),
+        transaction("2024-03-31", -40.0, TransactionCategory::Spesa),
+    ]);
+    let net_by_year = |start: u32| -> Vec<(NaiveDate, f64)> {
+        let net = extract_net_by_period(&registry, Period::Year(start), None, None).unwrap();
+        net.periods.into_iter().zip(net.net_amounts).collect()
+    };
+
+    assert_eq!(
+        net_by_year(4),
+        vec![(date("2022-04-01"), -10.0), (date("2023-04-01"), -60.0)]
+    );
+    assert_eq!(
+        net_by_year(1),
+        vec![(date("2023-01-01"), -30.0), (date("2024-01-01"), -40.0)]
+    );
+    assert_eq!(
+        net_by_year(12),
+        vec![(date("2022-12-01"), -30.0), (date("2023-12-01"), -40.0)]
+    );
+
+    for start in [0, 13] {
+        let error = extract_net_by_period(&registry, Period::Year(start), None, None)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error.downcast_ref::<PlotError>(),
+            Some(PlotError::InvalidParameter(_))
+        ));
+    }
+}
+
+#[test]
+fn low_balance_days() {
+    let mut registry = Registry::new(Some(vec![Account::new(
+        TransactionAccountName::Ale,
+        100.0,
+        date("2023-01-01"),
+    )]));
+    registry.add_batch(vec![
+        transaction("2023-01-01", -50.0, TransactionCategory::Spesa),
+        transaction("2023-01-02", -80.0, TransactionCategory::Spesa),
+        transaction("2023-01-05", 200.0, TransactionCategory::Stipendio),
+        transaction("2023-01-08", -250.0, TransactionCategory::Affitto),
+    ]);
+
+    let low_balance = extract_low_balance_days(&registry, 0.0, None, None).unwrap();
+    assert_eq!(
+        low_balance.days,
+        vec![
+            date("2023-01-02"),
+            date("2023-01-03"),
+            date("2023-01-04"),
+            date("2023-01-08")
+        ]
+    );
+    assert_eq!(low_balance.balances, vec![-30.0, -30.0, -30.0, -80.0]);
+
+    let low_balance = extract_low_balance_days(&registry, -100.0, None, None).unwrap();
+    assert!(low_balance.days.is_empty());
+}
+
+#[test]
+fn daily_initial_value_override() {
+    let registry = sample_registry();
+    let without =
+        extract_daily_transactions(&registry, None, None, None, None, false, None).unwrap();
+    let with_override =
+        extract_daily_transactions(&registry, None, None, None, None, true, Some(1000.0)).unwrap();
+
+    assert_eq!(with_override.days, without.days);
+    assert_eq!(with_override.amounts, without.amounts);
+    for (overridden, base) in with_override
+        .cumsum_amounts
+        .iter()
+        .zip(without.cumsum_amounts.iter())
+    {
+        assert!((overridden - base - 1000.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn monthly_gross_income() {
+    let mut registry = sample_registry();
+    registry.add_batch(vec![transaction(
+        "2023-03-10",
+        -25.0,
+        TransactionCategory::Spesa,
+    )]);
+    let monthly = monthy_extraction(&registry, None, None, None, None, None, None).unwrap();
+
+    assert_eq!(monthly.gross_income, vec![1500.0, 1550.0, 0.0]);
+    let rates: Vec<Option<f64>> = monthly
+        .net_income
+        .iter()
+        .zip(monthly.gross_income.iter())
+        .map(|(net, gross)| savings_rate(*net, *gross))
+        .collect();
+    assert_eq!(
+        rates,
+        vec![
+            Some(819.5 / 1500.0 * 100.0),
+            Some(908.0 / 1550.0 * 100.0),
+            None
+        ]
+    );
+}
+
+#[test]
+fn monthly_categories_config_order() {
+    let registry = sample_registry();
+    let config = CategoriesConfig::from_json(r#"["Treno", "spesa", "Stipendio"]"#).unwrap();
+    let monthly = monthy_extraction(
+        &registry,
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(&config.categories),
+    )
+    .unwrap();
+
+    // Affitto is missing from the config, then it follows the configured categories
+    assert_eq!(monthly.categories, vec!["Treno", "Spesa", "Affitto"]);
+    let unknown: Vec<TransactionCategory> = config
+        .unknown_transactions(&registry)
+        .into_iter()
+        .map(|t| t.category.clone())
+        .collect();
+    assert_eq!(
+        unknown,
+        vec![
+            TransactionCategory::Affitto,
+            TransactionCategory::Regalo,
+            TransactionCategory::Affitto
+        ]
+    );
+
+    let error = CategoriesConfig::from_json(r#"["Spesa", "Supermercato"]"#).unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<CategoriesConfigError>(),
+        Some(&CategoriesConfigError::UnknownCategory(String::from(
+            "Supermercato"
+        )))
+    );
+}
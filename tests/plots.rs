@@ -0,0 +1,609 @@
+mod common;
+
+use std::{collections::HashMap, str::FromStr};
+
+use assert_fs::TempDir;
+use common::{date, sample_registry, transaction};
+use plotters::style::RGBAColor;
+use realearning::{
+    model::{registry::Registry, transaction::TransactionCategory},
+    plots::{
+        plot_config::{OutputFormat, PlotConfig},
+        plot_errors::PlotError,
+        plot_registry::{
+            plot_account_cumulative, plot_budget_report, plot_category_pie,
+            plot_daily_transactions, plot_monthly_income_expense_bars, plot_monthly_net_bars,
+            plot_monthly_pies_separate, plot_monthly_report, plot_monthly_stacked_area,
+            plot_spending_heatmap, plot_weekday_spending, PieLabel, PieMode, PieStyle, SliceOrder,
+        },
+        plot_utils::{
+            palettes::{Palette, PaletteEnum, CB_FRIENDLY_PALETTE, RED_PALETTE},
+            resolution::{R4K, R720},
+            NumberFormat,
+        },
+    },
+};
+
+#[test]
+fn category_pie_modes() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let folder_path = folder.path().to_str().unwrap();
+    for mode in [PieMode::Both, PieMode::ExpensesOnly, PieMode::IncomeOnly] {
+        plot_category_pie(
+            &registry,
+            &PlotConfig::new(folder_path),
+            7,
+            mode,
+            SliceOrder::BySize,
+            PieLabel::Percentage,
+            &PieStyle::default(),
+        )
+        .unwrap();
+        assert!(folder.path().join("transaction_pie.png").is_file());
+    }
+}
+
+#[test]
+fn category_pie_by_name() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    for (prefix, from, to) in [
+        ("january", "2023-01-01", "2023-01-31"),
+        ("february", "2023-02-01", "2023-02-28"),
+    ] {
+        let config = PlotConfig::new(folder.path().to_str().unwrap())
+            .prefix(Some(prefix))
+            .date_range(Some((date(from), date(to))));
+        plot_category_pie(
+            &registry,
+            &config,
+            7,
+            PieMode::Both,
+            SliceOrder::ByName,
+            PieLabel::Percentage,
+            &PieStyle::default(),
+        )
+        .unwrap();
+        assert!(folder
+            .path()
+            .join(format!("{prefix}_transaction_pie.png"))
+            .is_file());
+    }
+}
+
+#[test]
+fn category_colors() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let rent_color = RGBAColor(0, 200, 0, 1.0);
+    let config = PlotConfig::new(folder.path().to_str().unwrap())
+        .category_colors(HashMap::from([(TransactionCategory::Affitto, rent_color)]));
+    // Affitto is an expense of both months, next to different categories
+    for (prefix, from, to) in [
+        ("january", "2023-01-01", "2023-01-31"),
+        ("february", "2023-02-01", "2023-02-28"),
+    ] {
+        plot_category_pie(
+            &registry,
+            &config
+                .clone()
+                .prefix(Some(prefix))
+                .date_range(Some((date(from), date(to)))),
+            7,
+            PieMode::ExpensesOnly,
+            SliceOrder::ByName,
+            PieLabel::Percentage,
+            &PieStyle::default(),
+        )
+        .unwrap();
+        let figure = image::open(folder.path().join(format!("{prefix}_transaction_pie.png")))
+            .unwrap()
+            .to_rgb8();
+        assert!(
+            figure.pixels().any(|pixel| pixel.0 == [0, 200, 0]),
+            "{prefix}"
+        );
+    }
+}
+
+#[test]
+fn multi_word_category_colors() {
+    let mut registry = Registry::new(None);
+    registry.add_batch(vec![
+        transaction("2023-01-03", -600.0, TransactionCategory::Affitto),
+        transaction("2023-01-10", -80.5, TransactionCategory::PranzoLavoro),
+        transaction("2023-01-12", -30.0, TransactionCategory::CartaDiCredito),
+    ]);
+    let folder = TempDir::new().unwrap();
+    let lunch_color = RGBAColor(0, 200, 0, 1.0);
+    let colors = HashMap::from([(TransactionCategory::PranzoLavoro, lunch_color)]);
+    let config = PlotConfig::new(folder.path().to_str().unwrap()).category_colors(colors);
+    // the categories are named by their variant, not by their spreadsheet name
+    assert_eq!(config.category_color("PranzoLavoro", 0), lunch_color);
+    assert_ne!(config.category_color("CartaDiCredito", 3), lunch_color);
+
+    plot_category_pie(
+        &registry,
+        &config,
+        7,
+        PieMode::ExpensesOnly,
+        SliceOrder::ByName,
+        PieLabel::Percentage,
+        &PieStyle::default(),
+    )
+    .unwrap();
+    let figure = image::open(folder.path().join("transaction_pie.png"))
+        .unwrap()
+        .to_rgb8();
+    assert!(figure.pixels().any(|pixel| pixel.0 == [0, 200, 0]));
+}
+
+#[test]
+fn category_pie_expenses_only() {
+    let mut registry = Registry::new(None);
+    registry.add_batch(vec![
+        transaction("2023-01-03", -600.0, TransactionCategory::Affitto),
+        transaction("2023-01-10", -80.5, TransactionCategory::Spesa),
+    ]);
+    let folder = TempDir::new().unwrap();
+    plot_category_pie(
+        &registry,
+        &PlotConfig::new(folder.path().to_str().unwrap()),
+        7,
+        PieMode::Both,
+        SliceOrder::BySize,
+        PieLabel::Percentage,
+        &PieStyle::default(),
+    )
+    .unwrap();
+    assert!(folder.path().join("transaction_pie.png").is_file());
+}
+
+#[test]
+fn daily_transactions_goal() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    // the goal goes beyond the cumulative sum, the chart range includes it
+    plot_daily_transactions(
+        &registry,
+        &PlotConfig::new(folder.path().to_str().unwrap()),
+        true,
+        Some((0.0, 5000.0)),
+    )
+    .unwrap();
+    assert!(folder.path().join("daily_transactions.png").is_file());
+}
+
+#[test]
+fn transparent_background() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let folder_path = folder.path().to_str().unwrap();
+    let config = PlotConfig::new(folder_path).transparent_background(true);
+    plot_category_pie(
+        &registry,
+        &config,
+        7,
+        PieMode::Both,
+        SliceOrder::BySize,
+        PieLabel::Percentage,
+        &PieStyle::default(),
+    )
+    .unwrap();
+    plot_daily_transactions(&registry, &config, false, None).unwrap();
+    plot_monthly_report(
+        &registry,
+        &config,
+        None,
+        PieLabel::Percentage,
+        &PieStyle::default(),
+        3,
+        false,
+        None,
+    )
+    .unwrap();
+
+    for file_name in [
+        "transaction_pie.png",
+        "daily_transactions.png",
+        "monthly_net_ts.png",
+        "monthly_category_pies.png",
+    ] {
+        let figure = image::open(folder.path().join(file_name))
+            .unwrap()
+            .to_rgba8();
+        // the corners are background while the plot is opaque
+        assert_eq!(figure.get_pixel(0, 0).0[3], 0, "{file_name}");
+        assert!(
+            figure.pixels().any(|pixel| pixel.0[3] == 255),
+            "{file_name}"
+        );
+        // the antialiased edges are blended with the transparency, not with a color
+        let edges: Vec<[u8; 4]> = figure
+            .pixels()
+            .map(|pixel| pixel.0)
+            .filter(|pixel| pixel[3] > 0 && pixel[3] < 255)
+            .collect();
+        assert!(!edges.is_empty(), "{file_name}");
+        assert!(
+            edges
+                .iter()
+                .all(|pixel| !(pixel[0] > 200 && pixel[1] < 60 && pixel[2] > 200)),
+            "{file_name}"
+        );
+    }
+}
+
+#[test]
+fn pie_labels() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let folder_path = folder.path().to_str().unwrap();
+    for label in [PieLabel::Percentage, PieLabel::Absolute, PieLabel::Both] {
+        plot_category_pie(
+            &registry,
+            &PlotConfig::new(folder_path),
+            7,
+            PieMode::Both,
+            SliceOrder::BySize,
+            label,
+            &PieStyle::default(),
+        )
+        .unwrap();
+        plot_monthly_report(
+            &registry,
+            &PlotConfig::new(folder_path),
+            Some(5),
+            label,
+            &PieStyle::default(),
+            3,
+            false,
+            None,
+        )
+        .unwrap();
+        assert!(folder.path().join("monthly_category_pies.png").is_file());
+    }
+}
+
+#[test]
+fn monthly_report_income_percentages() {
+    let mut registry = sample_registry();
+    // March has no income, then its savings rate is not labeled
+    registry.add_batch(vec![transaction(
+        "2023-03-10",
+        -25.0,
+        TransactionCategory::Spesa,
+    )]);
+    let folder = TempDir::new().unwrap();
+    plot_monthly_report(
+        &registry,
+        &PlotConfig::new(folder.path().to_str().unwrap()),
+        Some(5),
+        PieLabel::Percentage,
+        &PieStyle::default(),
+        3,
+        true,
+        None,
+    )
+    .unwrap();
+    assert!(folder.path().join("monthly_net_ts.png").is_file());
+    // the subfolder of the categories is created by the report
+    assert!(folder
+        .path()
+        .join("categories")
+        .join("monthly_Spesa.png")
+        .is_file());
+}
+
+#[test]
+fn monthly_report_pie_grid_cols() {
+    let mut registry = sample_registry();
+    registry.add_batch(
+        (3..=12)
+            .map(|month| {
+                transaction(
+                    &format!("2023-{month:02}-05"),
+                    -30.0,
+                    TransactionCategory::Spesa,
+                )
+            })
+            .collect(),
+    );
+    let folder = TempDir::new().unwrap();
+    let folder_path = folder.path().to_str().unwrap();
+    let plot = |pie_grid_cols| {
+        plot_monthly_report(
+            &registry,
+            &PlotConfig::new(folder_path),
+            Some(5),
+            PieLabel::Percentage,
+            &PieStyle::default(),
+            pie_grid_cols,
+            false,
+            None,
+        )
+    };
+
+    plot(4).unwrap();
+    assert!(folder.path().join("monthly_category_pies.png").is_file());
+    let error = plot(0).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<PlotError>(),
+        Some(PlotError::InvalidParameter(_))
+    ));
+}
+
+#[test]
+fn monthly_pies_separate() {
+    let mut registry = sample_registry();
+    registry.add_batch(
+        (3..=5)
+            .map(|month| {
+                transaction(
+                    &format!("2023-{month:02}-05"),
+                    -30.0,
+                    TransactionCategory::Spesa,
+                )
+            })
+            .collect(),
+    );
+    let folder = TempDir::new().unwrap();
+    plot_monthly_pies_separate(
+        &registry,
+        &PlotConfig::new(folder.path().to_str().unwrap()).prefix(Some("run")),
+        Some(5),
+        PieLabel::Both,
+        &PieStyle::default(),
+        None,
+    )
+    .unwrap();
+
+    let mut file_names: Vec<String> = std::fs::read_dir(folder.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    file_names.sort();
+    // January and February of the sample registry, then one month per new expense
+    assert_eq!(
+        file_names,
+        ["01", "02", "03", "04", "05"]
+            .iter()
+            .map(|month| format!("run_pie_2023-{month}.png"))
+            .collect::<Vec<String>>()
+    );
+}
+
+#[test]
+fn budget_report() {
+    let mut registry = sample_registry();
+    registry.add_batch(vec![transaction(
+        "2023-02-08",
+        -45.0,
+        TransactionCategory::PranzoLavoro,
+    )]);
+    let folder = TempDir::new().unwrap();
+    let budgets = HashMap::from([
+        (TransactionCategory::Affitto, 550.0),
+        (TransactionCategory::Spesa, 100.0),
+        (TransactionCategory::PranzoLavoro, 40.0),
+    ]);
+    plot_budget_report(
+        &registry,
+        &budgets,
+        &PlotConfig::new(folder.path().to_str().unwrap()),
+    )
+    .unwrap();
+    assert!(folder.path().join("budget_report.png").is_file());
+}
+
+#[test]
+fn output_prefix() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let folder_path = folder.path().to_str().unwrap();
+    for prefix in ["first", "second"] {
+        plot_category_pie(
+            &registry,
+            &PlotConfig::new(folder_path).prefix(Some(prefix)),
+            7,
+            PieMode::Both,
+            SliceOrder::BySize,
+            PieLabel::Percentage,
+            &PieStyle::default(),
+        )
+        .unwrap();
+    }
+    assert!(folder.path().join("first_transaction_pie.png").is_file());
+    assert!(folder.path().join("second_transaction_pie.png").is_file());
+}
+
+#[test]
+fn daily_transactions() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    for mark_extremes in [false, true] {
+        plot_daily_transactions(
+            &registry,
+            &PlotConfig::new(folder.path().to_str().unwrap()),
+            mark_extremes,
+            None,
+        )
+        .unwrap();
+        assert!(folder.path().join("daily_transactions.png").is_file());
+    }
+}
+
+#[test]
+fn monthly_stacked_area() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    plot_monthly_stacked_area(&registry, &PlotConfig::new(folder.path().to_str().unwrap()))
+        .unwrap();
+    assert!(folder.path().join("monthly_stacked_area.png").is_file());
+}
+
+#[test]
+fn monthly_net_bars() {
+    let mut registry = sample_registry();
+    // March has a negative net income
+    registry.add_batch(vec![transaction(
+        "2023-03-10",
+        -25.0,
+        TransactionCategory::Spesa,
+    )]);
+    let folder = TempDir::new().unwrap();
+    plot_monthly_net_bars(&registry, &PlotConfig::new(folder.path().to_str().unwrap())).unwrap();
+    assert!(folder.path().join("monthly_net_bars.png").is_file());
+}
+
+#[test]
+fn monthly_income_expense_bars() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    plot_monthly_income_expense_bars(&registry, &PlotConfig::new(folder.path().to_str().unwrap()))
+        .unwrap();
+    assert!(folder
+        .path()
+        .join("monthly_income_expense_bars.png")
+        .is_file());
+}
+
+#[test]
+fn weekday_spending() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    plot_weekday_spending(&registry, &PlotConfig::new(folder.path().to_str().unwrap())).unwrap();
+    assert!(folder.path().join("weekday_spending.png").is_file());
+}
+
+#[test]
+fn spending_heatmap() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    plot_spending_heatmap(&registry, &PlotConfig::new(folder.path().to_str().unwrap())).unwrap();
+    assert!(folder.path().join("spending_heatmap.png").is_file());
+}
+
+#[test]
+fn account_cumulative() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    plot_account_cumulative(&registry, &PlotConfig::new(folder.path().to_str().unwrap())).unwrap();
+    assert!(folder.path().join("account_cumulative.png").is_file());
+}
+
+#[test]
+fn pie_style_resolutions() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let folder_path = folder.path().to_str().unwrap();
+    for resolution in [R720, R4K] {
+        plot_category_pie(
+            &registry,
+            &PlotConfig::new(folder_path).resolution(resolution),
+            7,
+            PieMode::Both,
+            SliceOrder::BySize,
+            PieLabel::Both,
+            &PieStyle::default(),
+        )
+        .unwrap();
+    }
+    assert!(folder.path().join("transaction_pie.png").is_file());
+}
+
+#[test]
+fn plot_config() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let config = PlotConfig::new(folder.path().to_str().unwrap())
+        .prefix(Some("custom"))
+        .title_prefix("Ale")
+        .resolution((800, 600))
+        .output_format(OutputFormat::Jpeg)
+        .number_format(NumberFormat {
+            thousands_separator: Some(','),
+            decimal_separator: '.',
+        })
+        .date_format("%d/%m")
+        .accounts(None)
+        .date_range(Some((date("2023-01-01"), date("2023-01-31"))));
+    plot_daily_transactions(&registry, &config, true, None).unwrap();
+
+    let figure = image::open(folder.path().join("custom_daily_transactions.jpg")).unwrap();
+    assert_eq!((figure.width(), figure.height()), (800, 600));
+
+    // jpeg has no alpha channel for a transparent background
+    let error =
+        plot_daily_transactions(&registry, &config.transparent_background(true), true, None)
+            .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<PlotError>(),
+        Some(PlotError::InvalidParameter(_))
+    ));
+}
+
+#[test]
+fn invalid_date_format() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let config = PlotConfig::new(folder.path().to_str().unwrap()).date_format("%Q");
+    let error = plot_daily_transactions(&registry, &config, false, None).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<PlotError>(),
+        Some(PlotError::InvalidParameter(_))
+    ));
+    assert!(!folder.path().join("daily_transactions.png").exists());
+
+    // the other time axes share the date format of the config
+    for error in [
+        plot_account_cumulative(&registry, &config).unwrap_err(),
+        plot_monthly_stacked_area(&registry, &config).unwrap_err(),
+        plot_spending_heatmap(&registry, &config).unwrap_err(),
+    ] {
+        assert!(matches!(
+            error.downcast_ref::<PlotError>(),
+            Some(PlotError::InvalidParameter(_))
+        ));
+    }
+}
+
+#[test]
+fn short_palette() {
+    let first = RGBAColor(0, 0, 0, 1.0);
+    let second = RGBAColor(255, 255, 255, 1.0);
+    let palette = Palette::new(second, first, vec![first, second]);
+
+    assert_eq!(palette.color(0), first);
+    assert_eq!(palette.color(1), second);
+    assert_eq!(palette.color(2), first);
+    assert_eq!(palette.color(21), second);
+}
+
+#[test]
+fn long_palette() {
+    let colors: Vec<RGBAColor> = (0..30).map(|x| RGBAColor(x * 8, 0, 0, 1.0)).collect();
+    let palette = Palette::new(RED_PALETTE.background, RED_PALETTE.mesh, colors);
+
+    assert_eq!(palette.color(25), RGBAColor(200, 0, 0, 1.0));
+    assert_eq!(palette.color(30), RGBAColor(0, 0, 0, 1.0));
+}
+
+#[test]
+fn cb_friendly_palette() {
+    let palette = PaletteEnum::from_str("CB-Friendly").unwrap().palette();
+    assert_eq!(palette, &CB_FRIENDLY_PALETTE);
+    assert_eq!(PaletteEnum::CbFriendly.to_string(), "cb-friendly");
+
+    for (i, color) in palette.colors.iter().enumerate() {
+        assert!(!palette.colors[i + 1..].contains(color));
+        assert_ne!(*color, palette.background);
+    }
+}
+
+#[test]
+fn default_palette() {
+    assert_eq!(Palette::default(), RED_PALETTE);
+    assert_eq!(RED_PALETTE.colors.len(), 20);
+}
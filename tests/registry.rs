@@ -1,7 +1,835 @@
-use realearning::model::registry::Registry;
+mod common;
+
+use assert_fs::{prelude::*, TempDir};
+use common::{date, sample_registry, transaction};
+use polars::prelude::*;
+use realearning::model::{
+    account::{Account, TransactionAccountName},
+    registry::{AccountSummary, BalanceDiscrepancy, Registry},
+    transaction::{Sign, TransactionCategory, TransactionEvent},
+};
+use std::{collections::HashMap, fs::File, time::Instant};
 
 #[test]
 fn empty_registry() {
     let r = Registry::new(None);
     assert_eq!(r.get_accounts().len(), 0)
 }
+
+#[test]
+fn len() {
+    let registry = Registry::new(Some(vec![Account::new(
+        TransactionAccountName::Ale,
+        100.0,
+        date("2023-01-01"),
+    )]));
+    assert!(registry.is_empty());
+    assert_eq!(registry.len(), 0);
+
+    let registry = sample_registry();
+    assert!(!registry.is_empty());
+    assert_eq!(registry.len(), 7);
+}
+
+#[test]
+fn from_transactions() {
+    let mut giulia_expense = transaction("2023-01-05", -30.0, TransactionCategory::Pasto);
+    giulia_expense.account = TransactionAccountName::Giulia;
+    // transactions out of date order
+    let registry = Registry::from_transactions(
+        vec![
+            transaction("2023-02-01", 1500.0, TransactionCategory::Stipendio),
+            giulia_expense,
+            transaction("2023-01-03", -600.0, TransactionCategory::Affitto),
+        ],
+        Some(vec![Account::new(
+            TransactionAccountName::Ale,
+            100.0,
+            date("2023-01-01"),
+        )]),
+    );
+
+    assert_eq!(
+        registry.iter().map(|t| t.date).collect::<Vec<_>>(),
+        vec![date("2023-01-03"), date("2023-01-05"), date("2023-02-01")]
+    );
+    assert_eq!(
+        registry.accounts_summary(),
+        vec![
+            AccountSummary {
+                name: String::from("Ale"),
+                current_value: 1000.0,
+                initial_value: 100.0,
+                transactions: 2,
+            },
+            AccountSummary {
+                name: String::from("Giulia"),
+                current_value: -30.0,
+                initial_value: -30.0,
+                transactions: 1,
+            },
+        ]
+    );
+}
+
+#[test]
+fn account_display_name() {
+    let card = TransactionAccountName::CartaAle;
+    assert_eq!(card.to_string(), "carta ale");
+    assert_eq!(card.display_name(), "Carta di Credito Ale");
+    assert_ne!(card.display_name(), card.to_string());
+    // the display name is not an identifier of the raw files
+    assert!(card
+        .display_name()
+        .parse::<TransactionAccountName>()
+        .is_err());
+    assert!("carta ale".parse::<TransactionAccountName>() == Ok(card));
+
+    for name in ["carta ale", "CartaAle"] {
+        assert_eq!(
+            TransactionAccountName::display_name_of(name),
+            "Carta di Credito Ale"
+        );
+    }
+    assert_eq!(TransactionAccountName::display_name_of("Ale"), "Ale");
+    assert_eq!(
+        TransactionAccountName::display_name_of("unknown"),
+        "unknown"
+    );
+}
+
+fn described(
+    date_str: &str,
+    amount: f32,
+    category: TransactionCategory,
+    description: &str,
+) -> TransactionEvent {
+    let mut event = transaction(date_str, amount, category);
+    event.description = Some(String::from(description));
+    event
+}
+
+fn descriptions(registry: &Registry) -> Vec<String> {
+    registry
+        .iter()
+        .map(|t| t.description.clone().unwrap_or_default())
+        .collect()
+}
+
+#[test]
+fn same_date_transactions_keep_insertion_order() {
+    let batch = || {
+        vec![
+            described("2023-05-10", -1.0, TransactionCategory::Spesa, "late"),
+            described("2023-05-09", -2.0, TransactionCategory::Spesa, "first"),
+            described("2023-05-09", -3.0, TransactionCategory::Spesa, "second"),
+        ]
+    };
+
+    for _ in 0..5 {
+        let mut registry = Registry::from_transactions(batch(), None);
+        registry.add_single(described(
+            "2023-05-09",
+            -4.0,
+            TransactionCategory::Spesa,
+            "third",
+        ));
+        assert_eq!(
+            descriptions(&registry),
+            vec!["first", "second", "third", "late"]
+        );
+
+        let merged = registry + Registry::from_transactions(batch(), None);
+        assert_eq!(
+            descriptions(&merged),
+            vec!["first", "second", "third", "first", "second", "late", "late"]
+        );
+    }
+}
+
+#[test]
+fn extend() {
+    let mut registry = Registry::from_transactions(
+        vec![
+            described("2023-01-10", -20.0, TransactionCategory::Spesa, "first"),
+            described(
+                "2023-02-01",
+                1000.0,
+                TransactionCategory::Stipendio,
+                "salary",
+            ),
+        ],
+        Some(vec![Account::new(
+            TransactionAccountName::Ale,
+            100.0,
+            date("2023-01-01"),
+        )]),
+    );
+    let mut giulia_expense = described("2023-01-20", -30.0, TransactionCategory::Pasto, "lunch");
+    giulia_expense.account = TransactionAccountName::Giulia;
+    let other = Registry::from_transactions(
+        vec![
+            described("2023-01-10", -5.0, TransactionCategory::Spesa, "second"),
+            giulia_expense,
+        ],
+        None,
+    );
+
+    registry.extend(other);
+
+    assert_eq!(
+        descriptions(&registry),
+        vec!["first", "second", "lunch", "salary"]
+    );
+    assert_eq!(
+        registry
+            .accounts_summary()
+            .iter()
+            .map(|a| (a.name.as_str(), a.current_value, a.transactions))
+            .collect::<Vec<_>>(),
+        vec![("Ale", 1080.0, 3), ("Giulia", -30.0, 1)]
+    );
+}
+
+#[test]
+fn recategorize_by_keywords() {
+    let mut registry = Registry::from_transactions(
+        vec![
+            described(
+                "2023-05-09",
+                -20.0,
+                TransactionCategory::Uncategorized,
+                "ESSELUNGA Milano",
+            ),
+            described(
+                "2023-05-09",
+                -3.0,
+                TransactionCategory::Uncategorized,
+                "Biglietto Trenord",
+            ),
+            described(
+                "2023-05-09",
+                -8.0,
+                TransactionCategory::Uncategorized,
+                "cinema",
+            ),
+            transaction("2023-05-09", -5.0, TransactionCategory::Uncategorized),
+            described("2023-05-09", -9.0, TransactionCategory::Pasto, "esselunga"),
+        ],
+        None,
+    );
+
+    registry.recategorize_by_keywords(&[
+        (String::from("esselunga"), TransactionCategory::Spesa),
+        (String::from("TRENORD"), TransactionCategory::Treno),
+    ]);
+
+    let categories: Vec<String> = registry.iter().map(|t| t.category.to_string()).collect();
+    assert_eq!(
+        categories,
+        vec!["Spesa", "Treno", "Uncategorized", "Uncategorized", "Pasto"]
+    );
+}
+
+#[test]
+fn date_range() {
+    assert_eq!(Registry::new(None).date_range(), None);
+
+    let mut registry = sample_registry();
+    assert_eq!(
+        registry.date_range(),
+        Some((date("2023-01-01"), date("2023-02-15")))
+    );
+    registry.add_single(transaction("2022-12-31", -10.0, TransactionCategory::Pasto));
+    assert_eq!(
+        registry.date_range(),
+        Some((date("2022-12-31"), date("2023-02-15")))
+    );
+}
+
+#[test]
+fn dedup() {
+    let mut registry = sample_registry() + sample_registry();
+    assert_eq!(registry.iter().count(), 14);
+
+    assert_eq!(registry.dedup(), 7);
+    assert_eq!(registry.iter().count(), 7);
+    assert!(registry
+        .iter()
+        .zip(sample_registry().iter())
+        .all(|(t, expected)| t == expected));
+    assert_eq!(registry.dedup(), 0);
+
+    let mut other_description = transaction("2023-01-10", -80.5, TransactionCategory::Spesa);
+    other_description.description = Some(String::from("esselunga"));
+    registry.add_single(other_description);
+    assert_eq!(registry.dedup(), 0);
+}
+
+#[test]
+fn recompute_balances_after_dedup() {
+    let mut registry = sample_registry();
+    registry.add_batch(sample_registry().iter().cloned().collect());
+    assert_eq!(registry.accounts_summary()[0].current_value, 3455.0);
+
+    assert_eq!(registry.dedup(), 7);
+    registry.recompute_balances();
+    let summary = &registry.accounts_summary()[0];
+    assert_eq!(summary.current_value, 1727.5);
+    assert_eq!(summary.initial_value, 1500.0);
+
+    // the initial value of an account given to the registry is before its transactions
+    let mut registry = Registry::new(Some(vec![Account::new(
+        TransactionAccountName::Ale,
+        100.0,
+        date("2023-01-01"),
+    )]));
+    registry.add_batch(sample_registry().iter().cloned().collect());
+    registry.add_batch(sample_registry().iter().cloned().collect());
+    registry.dedup();
+    registry.recompute_balances();
+    let summary = &registry.accounts_summary()[0];
+    assert_eq!(summary.current_value, 1827.5);
+    assert_eq!(summary.initial_value, 100.0);
+}
+
+#[test]
+fn recompute_balances_out_of_order() {
+    let mut registry = Registry::new(None);
+    registry.add_single(transaction("2023-02-10", -50.0, TransactionCategory::Spesa));
+    registry.add_single(transaction("2023-01-05", -20.0, TransactionCategory::Spesa));
+    assert_eq!(registry.accounts_summary()[0].current_value, -70.0);
+
+    registry.recompute_balances();
+    let summary = &registry.accounts_summary()[0];
+    assert_eq!(summary.current_value, -70.0);
+    assert_eq!(summary.initial_value, -20.0);
+
+    let expenses = registry.filter(|t| t.amount < 0.0);
+    assert_eq!(expenses.accounts_summary()[0].current_value, -70.0);
+
+    // the first transaction of the account is dropped
+    let mut to_card = transaction("2023-02-03", -200.0, TransactionCategory::Banca);
+    to_card.account = TransactionAccountName::Ale;
+    let mut from_ale = transaction("2023-02-03", 200.0, TransactionCategory::Banca);
+    from_ale.account = TransactionAccountName::CartaAle;
+    let mut refund = transaction("2023-02-04", 150.0, TransactionCategory::Banca);
+    refund.account = TransactionAccountName::CartaAle;
+    registry.add_batch(vec![to_card, from_ale, refund]);
+    assert_eq!(registry.drop_internal_transfers(), 2);
+    registry.recompute_balances();
+    let summary = registry.accounts_summary();
+    assert_eq!(summary[0].current_value, -70.0);
+    assert_eq!(summary[1].current_value, 150.0);
+    assert_eq!(summary[1].initial_value, 150.0);
+
+    registry.rename_account("carta ale", "ale").unwrap();
+    assert_eq!(registry.accounts_summary()[0].current_value, 80.0);
+}
+
+#[test]
+fn rename_account() {
+    let mut registry = Registry::new(Some(vec![Account::new(
+        TransactionAccountName::Ale,
+        100.0,
+        date("2023-01-01"),
+    )]));
+    registry.add_batch(sample_registry().iter().cloned().collect());
+    let mut card = transaction("2023-01-20", -30.0, TransactionCategory::Pasto);
+    card.account = TransactionAccountName::CartaAle;
+    let mut cash = transaction("2023-02-20", -5.0, TransactionCategory::Pasto);
+    cash.account = TransactionAccountName::Contante;
+    registry.add_batch(vec![card, cash]);
+
+    // the card is merged into the existing account
+    registry.rename_account("carta ale", "ale").unwrap();
+    assert!(registry
+        .iter()
+        .all(|t| t.account != TransactionAccountName::CartaAle));
+    let summary = registry.accounts_summary();
+    assert_eq!(
+        summary
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["Ale", "Contante"]
+    );
+    assert_eq!(summary[0].initial_value, 100.0);
+    assert_eq!(summary[0].current_value, 100.0 + 1727.5 - 30.0);
+    assert_eq!(summary[0].transactions, 8);
+
+    // the cash is moved to a new account opened by its transaction
+    registry.rename_account("contante", "carta giulia").unwrap();
+    let summary = registry.accounts_summary();
+    assert_eq!(summary[1].name, "Carta di Credito Giulia");
+    assert_eq!(summary[1].current_value, -5.0);
+    assert_eq!(summary[1].transactions, 1);
+
+    assert!(registry.rename_account("ale", "unknown").is_err());
+}
+
+#[test]
+fn filter() {
+    let mut registry = Registry::new(Some(vec![Account::new(
+        TransactionAccountName::Giulia,
+        100.0,
+        date("2023-01-01"),
+    )]));
+    registry.add_batch(sample_registry().iter().cloned().collect());
+    let mut gift = transaction("2023-02-05", 200.0, TransactionCategory::Regalo);
+    gift.account = TransactionAccountName::Giulia;
+    registry.add_single(gift);
+
+    let filtered = registry.filter(|t| t.amount > 100.0);
+    assert_eq!(filtered.iter().count(), 3);
+    assert!(filtered.iter().all(|t| t.amount > 100.0));
+    let summary = filtered.accounts_summary();
+    assert_eq!(
+        summary
+            .iter()
+            .map(|a| (a.name.as_str(), a.current_value, a.transactions))
+            .collect::<Vec<_>>(),
+        vec![("Ale", 3000.0, 2), ("Giulia", 300.0, 1)]
+    );
+    // the original registry is untouched
+    assert_eq!(registry.iter().count(), 8);
+}
+
+#[test]
+fn drop_internal_transfers() {
+    let mut registry = sample_registry();
+    let mut to_card = transaction("2023-02-03", -200.0, TransactionCategory::Banca);
+    to_card.account = TransactionAccountName::Ale;
+    let mut from_ale = transaction("2023-02-03", 200.0, TransactionCategory::Banca);
+    from_ale.account = TransactionAccountName::CartaAle;
+    // same amount on another date is not a transfer
+    let mut refund = transaction("2023-02-04", 200.0, TransactionCategory::Banca);
+    refund.account = TransactionAccountName::CartaAle;
+    registry.add_batch(vec![to_card, from_ale, refund]);
+    let accounts = registry.accounts_summary();
+
+    assert_eq!(registry.drop_internal_transfers(), 2);
+    assert_eq!(registry.iter().count(), 8);
+    assert!(registry
+        .iter()
+        .all(|t| t.amount.abs() != 200.0 || t.date == date("2023-02-04")));
+    assert_eq!(
+        registry.accounts_summary()[0].current_value,
+        accounts[0].current_value
+    );
+    assert_eq!(registry.drop_internal_transfers(), 0);
+}
+
+#[test]
+fn largest_expenses() {
+    let registry = sample_registry();
+    let largest = registry.largest_transactions(2, Sign::Expense);
+
+    let amounts: Vec<f32> = largest.iter().map(|t| t.amount).collect();
+    assert_eq!(amounts, vec![-600.0, -600.0]);
+}
+
+#[test]
+fn largest_transactions_sorted() {
+    let registry = sample_registry();
+    let largest = registry.largest_transactions(10, Sign::Any);
+
+    assert_eq!(largest.len(), 7);
+    assert!(largest
+        .windows(2)
+        .all(|w| w[0].amount.abs() >= w[1].amount.abs()));
+
+    let incomes = registry.largest_transactions(10, Sign::Income);
+    assert_eq!(incomes.len(), 3);
+    assert!(incomes.iter().all(|t| t.amount > 0.0));
+}
+
+#[test]
+fn largest_transactions_filtered() {
+    let registry = sample_registry();
+    let (from, to) = (date("2023-02-01"), date("2023-02-28"));
+    let largest =
+        registry.largest_transactions_filtered(10, Sign::Expense, None, Some((&from, &to)));
+
+    let amounts: Vec<f32> = largest.iter().map(|t| t.amount).collect();
+    assert_eq!(amounts, vec![-600.0, -42.0]);
+
+    let accounts = vec![String::from("Giulia")];
+    assert!(registry
+        .largest_transactions_filtered(10, Sign::Any, Some(&accounts), None)
+        .is_empty());
+
+    // accounts are matched by their identifier or serialized name
+    let mut registry = sample_registry();
+    registry.add_single(TransactionEvent::new(
+        date("2023-02-20"),
+        -5.5,
+        TransactionCategory::Pasto,
+        None,
+        TransactionAccountName::CartaAle,
+    ));
+    for name in ["carta ale", "CartaAle"] {
+        let accounts = vec![String::from(name)];
+        let largest = registry.largest_transactions_filtered(10, Sign::Any, Some(&accounts), None);
+        assert_eq!(largest.len(), 1);
+        assert_eq!(largest[0].account, TransactionAccountName::CartaAle);
+    }
+    let largest =
+        registry.largest_transactions_filtered(10, Sign::Any, Some(&registry.get_accounts()), None);
+    assert_eq!(largest.len(), registry.len());
+}
+
+#[test]
+fn category_totals() {
+    let registry = sample_registry();
+    let totals = registry.category_totals(None);
+
+    assert_eq!(totals.len(), 5);
+    assert_eq!(totals[&TransactionCategory::Stipendio], 3000.0);
+    assert_eq!(totals[&TransactionCategory::Affitto], -1200.0);
+    assert_eq!(totals[&TransactionCategory::Spesa], -80.5);
+    assert_eq!(totals[&TransactionCategory::Regalo], 50.0);
+    assert_eq!(totals[&TransactionCategory::Treno], -42.0);
+
+    let (from, to) = (date("2023-02-01"), date("2023-02-28"));
+    let totals = registry.category_totals(Some((&from, &to)));
+    assert_eq!(totals.len(), 4);
+    assert_eq!(totals[&TransactionCategory::Affitto], -600.0);
+    assert!(!totals.contains_key(&TransactionCategory::Spesa));
+}
+
+#[test]
+fn accounts_summary() {
+    let mut registry = sample_registry();
+    let mut giulia_expense = transaction("2023-02-20", -30.0, TransactionCategory::Pasto);
+    giulia_expense.account = TransactionAccountName::Giulia;
+    let mut giulia_refund = transaction("2023-02-25", 10.0, TransactionCategory::Pasto);
+    giulia_refund.account = TransactionAccountName::Giulia;
+    registry.add_batch(vec![giulia_expense, giulia_refund]);
+
+    assert_eq!(
+        registry.accounts_summary(),
+        vec![
+            AccountSummary {
+                name: String::from("Ale"),
+                current_value: 1727.5,
+                initial_value: 1500.0,
+                transactions: 7,
+            },
+            AccountSummary {
+                name: String::from("Giulia"),
+                current_value: -20.0,
+                initial_value: -30.0,
+                transactions: 2,
+            },
+        ]
+    );
+}
+
+#[test]
+fn transaction_count_by_account() {
+    let mut registry = sample_registry();
+    let mut contante = transaction("2023-02-20", -5.0, TransactionCategory::Pasto);
+    contante.account = TransactionAccountName::Contante;
+    let mut buono_pasto = transaction("2023-02-21", -8.0, TransactionCategory::Pasto);
+    buono_pasto.account = TransactionAccountName::BuonoPasto;
+    let mut other_buono_pasto = transaction("2023-02-22", -8.0, TransactionCategory::Pasto);
+    other_buono_pasto.account = TransactionAccountName::BuonoPasto;
+    registry.add_batch(vec![contante, buono_pasto, other_buono_pasto]);
+
+    let counts = registry.transaction_count_by_account();
+    assert_eq!(counts.len(), 3);
+    assert_eq!(counts["Ale"], 7);
+    assert_eq!(counts["buono pasto"], 2);
+    assert_eq!(counts["Contante"], 1);
+}
+
+#[test]
+fn split_by_account() {
+    let mut registry = sample_registry();
+    let mut giulia_expense = transaction("2023-02-20", -30.0, TransactionCategory::Pasto);
+    giulia_expense.account = TransactionAccountName::Giulia;
+    let mut giulia_refund = transaction("2023-02-25", 10.0, TransactionCategory::Pasto);
+    giulia_refund.account = TransactionAccountName::Giulia;
+    registry.add_batch(vec![giulia_expense, giulia_refund]);
+
+    let split = registry.split_by_account();
+    assert_eq!(split.len(), 2);
+    assert_eq!(
+        split.values().map(|r| r.iter().count()).sum::<usize>(),
+        registry.iter().count()
+    );
+    assert_eq!(split["Ale"].iter().count(), 7);
+    assert_eq!(split["Ale"].get_accounts(), vec!["Ale"]);
+    assert_eq!(split["Ale"].get_initial_account_values(None), 1500.0);
+    assert_eq!(split["Giulia"].iter().count(), 2);
+    assert_eq!(split["Giulia"].get_initial_account_values(None), -30.0);
+}
+
+#[test]
+fn validate_matching_balances() {
+    let registry = sample_registry();
+    let expected = HashMap::from([(String::from("Ale"), 1727.49)]);
+
+    assert!(registry.validate_balances(&expected, 0.05).is_empty());
+}
+
+#[test]
+fn validate_mismatching_balances() {
+    let registry = sample_registry();
+    let expected = HashMap::from([
+        (String::from("Ale"), 1700.0),
+        (String::from("Giulia"), 10.0),
+    ]);
+
+    let discrepancies = registry.validate_balances(&expected, 0.05);
+    assert_eq!(discrepancies.len(), 2);
+    assert_eq!(discrepancies[0].account, "Ale");
+    assert!((discrepancies[0].computed.unwrap() - 1727.5).abs() < 1e-3);
+    assert_eq!(
+        discrepancies[1],
+        BalanceDiscrepancy {
+            account: String::from("Giulia"),
+            expected: 10.0,
+            computed: None,
+        }
+    );
+}
+
+#[test]
+fn display_empty_registry() {
+    let registry = Registry::new(None);
+    assert_eq!(registry.to_string(), "The registry has 0 accounts:\n\n");
+}
+
+#[test]
+fn display_few_transactions() {
+    let mut registry = Registry::new(None);
+    registry.add_batch(vec![
+        transaction("2023-01-01", 1500.0, TransactionCategory::Stipendio),
+        transaction("2023-01-03", -600.0, TransactionCategory::Affitto),
+        transaction("2023-01-10", -80.5, TransactionCategory::Spesa),
+    ]);
+
+    assert_eq!(
+        registry.to_string(),
+        "The registry has 1 accounts:\n\n\
+         \t> Ale:\t819.5€\n\
+         \n\nShowing the last 3 of 3 transactions:\n\n\
+         \t- Transaction on date 2023-01-01 of category Stipendio, amount: 1500€, account: Ale, description: missing\n\
+         \t- Transaction on date 2023-01-03 of category Affitto, amount: -600€, account: Ale, description: missing\n\
+         \t- Transaction on date 2023-01-10 of category Spesa, amount: -80.5€, account: Ale, description: missing\n"
+    );
+}
+
+#[test]
+fn display_last_transactions() {
+    let mut registry = Registry::new(None);
+    registry.add_batch(
+        (1..=10)
+            .map(|day| {
+                transaction(
+                    &format!("2023-01-{:02}", day),
+                    -(day as f32),
+                    TransactionCategory::Spesa,
+                )
+            })
+            .collect(),
+    );
+    let formatted = registry.to_string();
+
+    assert!(formatted.contains("Showing the last 5 of 10 transactions:"));
+    let shown: Vec<&str> = formatted
+        .lines()
+        .filter(|line| line.starts_with("\t- "))
+        .collect();
+    assert_eq!(shown.len(), 5);
+    assert!(shown[0].contains("2023-01-06"));
+    assert!(shown[4].contains("2023-01-10"));
+}
+
+/// Compares the direct dataframe build with the one through json,
+/// run it with `cargo test --release --test registry -- --ignored --nocapture`
+#[test]
+#[ignore = "benchmark"]
+fn dataframe_benchmark() {
+    let categories = [
+        TransactionCategory::Spesa,
+        TransactionCategory::Stipendio,
+        TransactionCategory::CartaDiCredito,
+        TransactionCategory::Affitto,
+    ];
+    let accounts = [
+        TransactionAccountName::Ale,
+        TransactionAccountName::Giulia,
+        TransactionAccountName::CartaAle,
+    ];
+    let transactions: Vec<TransactionEvent> = (0..50_000)
+        .map(|i| {
+            TransactionEvent::new(
+                date("2020-01-01") + chrono::Duration::days(i / 20),
+                (i % 1000) as f32 / 100.0 - 5.0,
+                categories[i as usize % categories.len()].clone(),
+                (i % 3 == 0).then(|| format!("transaction {i}")),
+                accounts[i as usize % accounts.len()].clone(),
+            )
+        })
+        .collect();
+    let registry = Registry::from_transactions(transactions, None);
+
+    let start = Instant::now();
+    let direct = registry.to_dataframe().unwrap();
+    let direct_time = start.elapsed();
+    let start = Instant::now();
+    let json = registry.to_dataframe_json().unwrap();
+    let json_time = start.elapsed();
+
+    println!("to_dataframe: {direct_time:?}, to_dataframe_json: {json_time:?}");
+    assert_eq!(direct.height(), 50_000);
+    assert!(direct.frame_equal_missing(&json));
+}
+
+#[test]
+fn parquet_roundtrip() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let path = folder.child("registry.parquet");
+    registry.to_parquet(path.to_str().unwrap()).unwrap();
+
+    let df = ParquetReader::new(File::open(path.path()).unwrap())
+        .finish()
+        .unwrap();
+    assert_eq!(df.shape(), registry.to_dataframe().unwrap().shape());
+}
+
+#[test]
+fn filtered_dataframe() {
+    let registry = sample_registry();
+    let (from, to) = (date("2023-01-03"), date("2023-02-01"));
+
+    let df = registry
+        .filtered_dataframe(None, Some((&from, &to)))
+        .unwrap();
+    assert_eq!(df.height(), 3);
+    assert_eq!(df.width(), registry.to_dataframe().unwrap().width());
+
+    let accounts = vec![String::from("Giulia")];
+    let df = registry.filtered_dataframe(Some(&accounts), None).unwrap();
+    assert_eq!(df.height(), 0);
+
+    // accounts are filtered by their identifier or serialized name
+    let mut registry = sample_registry();
+    registry.add_single(TransactionEvent::new(
+        date("2023-02-20"),
+        -5.5,
+        TransactionCategory::Pasto,
+        None,
+        TransactionAccountName::CartaAle,
+    ));
+    let accounts = vec![String::from("CartaAle")];
+    let df = registry.filtered_dataframe(Some(&accounts), None).unwrap();
+    assert_eq!(df.height(), 1);
+    let accounts = vec![String::from("carta ale")];
+    let df = registry.filtered_dataframe(Some(&accounts), None).unwrap();
+    assert_eq!(df.height(), 1);
+    let df = registry
+        .filtered_dataframe(Some(&registry.get_accounts()), None)
+        .unwrap();
+    assert_eq!(df.height(), registry.len());
+}
+
+#[test]
+fn append_to_csv() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let path = folder.child("registry.csv");
+    let path = path.to_str().unwrap();
+    registry.append_to_csv(path).unwrap();
+    registry.append_to_csv(path).unwrap();
+
+    let appended = Registry::from_csv(path).unwrap();
+    assert_eq!(appended.iter().count(), 14);
+
+    registry.to_csv(path).unwrap();
+    assert_eq!(Registry::from_csv(path).unwrap().iter().count(), 7);
+}
+
+#[test]
+fn from_csv_text_amounts() {
+    let folder = TempDir::new().unwrap();
+    let file = folder.child("registry.csv");
+    file.write_str(
+        "date,amount,category,description,account\n\
+         2023-01-01,\"1.500,00 €\",Stipendio,,Ale\n\
+         2023-01-03,-600.5,Affitto,,Ale\n\
+         2023-01-10,-80,Spesa,Coop,Ale\n",
+    )
+    .unwrap();
+
+    let registry = Registry::from_csv(file.to_str().unwrap()).unwrap();
+    let amounts: Vec<f32> = registry.iter().map(|t| t.amount).collect();
+    assert_eq!(amounts, vec![1500.0, -600.5, -80.0]);
+
+    file.write_str("date,amount,category,description,account\n2023-01-01,abc,Spesa,,Ale\n")
+        .unwrap();
+    assert!(Registry::from_csv(file.to_str().unwrap()).is_err());
+}
+
+#[test]
+fn sign_anomalies() {
+    let mut registry = sample_registry();
+    assert!(registry.sign_anomalies().is_empty());
+
+    registry.add_batch(vec![
+        transaction("2023-02-10", -1500.0, TransactionCategory::Stipendio),
+        transaction("2023-02-11", 30.0, TransactionCategory::Spesa),
+        transaction("2023-02-12", -30.0, TransactionCategory::Regalo),
+    ]);
+    let anomalies: Vec<(f32, String)> = registry
+        .sign_anomalies()
+        .iter()
+        .map(|t| (t.amount, t.category.to_string()))
+        .collect();
+    assert_eq!(
+        anomalies,
+        vec![
+            (-1500.0, String::from("Stipendio")),
+            (30.0, String::from("Spesa"))
+        ]
+    );
+}
+
+#[test]
+fn to_table_string() {
+    let mut registry = sample_registry();
+    registry.add_single(TransactionEvent::new(
+        date("2023-02-20"),
+        -5.5,
+        TransactionCategory::Pasto,
+        Some(String::from("pranzo")),
+        TransactionAccountName::CartaAle,
+    ));
+
+    let table = registry.to_table_string(Some(3));
+    let lines: Vec<&str> = table.lines().collect();
+    // header, separator and the last three transactions
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("date"));
+    assert!(lines[4].contains("Carta di Credito Ale") && lines[4].ends_with("pranzo"));
+
+    let separators = |line: &str| -> Vec<usize> {
+        line.char_indices()
+            .filter(|(_, c)| *c == '|' || *c == '+')
+            .map(|(i, _)| i)
+            .collect()
+    };
+    for line in &lines[1..] {
+        assert_eq!(separators(line), separators(lines[0]));
+    }
+    // amounts are right aligned
+    let amount_end = separators(lines[0])[3] - 1;
+    assert_eq!(&lines[2][amount_end - 7..amount_end], "-600.00");
+    assert_eq!(&lines[4][amount_end - 5..amount_end], "-5.50");
+
+    assert_eq!(registry.to_table_string(None).lines().count(), 10);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use assert_fs::{prelude::*, TempDir};
+use common::sample_registry;
+use realearning::{
+    io::summary::{monthly_summary, RegistrySummary},
+    plots::{extraction::monthy_extraction, plot_utils::NumberFormat},
+};
+
+#[test]
+fn monthly_summary_contains_months() {
+    let registry = sample_registry();
+    let monthly = monthy_extraction(&registry, None, None, None, None, None, None).unwrap();
+    let summary = monthly_summary(&monthly, &NumberFormat::default());
+
+    let lines: Vec<&str> = summary.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("Month"));
+    assert!(lines[1].starts_with("2023-01"));
+    assert!(lines[1].contains("819.50"));
+    assert!(lines[1].contains("Affitto (-600.00)"));
+    assert!(lines[2].starts_with("2023-02"));
+    assert!(lines[2].contains("908.00"));
+}
+
+#[test]
+fn registry_summary_json() {
+    let registry = sample_registry();
+    let folder = TempDir::new().unwrap();
+    let path = folder.child("summary.json");
+    RegistrySummary::new(&registry)
+        .unwrap()
+        .to_json_file(path.to_str().unwrap())
+        .unwrap();
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(path.path()).unwrap()).unwrap();
+    for key in [
+        "total_income",
+        "total_expense",
+        "net",
+        "category_totals",
+        "monthly_net_income",
+    ] {
+        assert!(json.get(key).is_some(), "missing key {}", key);
+    }
+    assert_eq!(json["total_income"], 3050.0);
+    assert_eq!(json["net"], 1727.5);
+    assert_eq!(json["category_totals"]["Affitto"], -1200.0);
+    assert_eq!(json["monthly_net_income"]["net_amounts"][0], 819.5);
+}